@@ -0,0 +1,307 @@
+//! Typed ancillary data (control messages) for UDP sockets.
+
+use std::fmt;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ptr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cmsg::{bytes_of, next_cmsg};
+
+/// Ancillary data to send along with a message.
+///
+/// Setting the same kind of control message twice replaces the previous value.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::SendAncillary;
+/// use std::net::Ipv4Addr;
+///
+/// let mut ancillary = SendAncillary::new();
+/// ancillary.set_source_v4(Ipv4Addr::LOCALHOST, 0).set_ttl(16);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SendAncillary {
+    /// Control messages as `(level, type, data)` triples.
+    messages: Vec<(libc::c_int, libc::c_int, Vec<u8>)>,
+}
+
+impl SendAncillary {
+    /// Creates an empty set of control messages.
+    pub fn new() -> SendAncillary {
+        SendAncillary::default()
+    }
+
+    /// Sends an IPv4 datagram from the given source address and interface (`IP_PKTINFO`).
+    ///
+    /// An interface index of 0 lets the OS pick the interface based on the routing table.
+    pub fn set_source_v4(&mut self, addr: Ipv4Addr, ifindex: u32) -> &mut SendAncillary {
+        let info = libc::in_pktinfo {
+            ipi_ifindex: ifindex as _,
+            ipi_spec_dst: libc::in_addr {
+                s_addr: u32::from(addr).to_be(),
+            },
+            ipi_addr: libc::in_addr { s_addr: 0 },
+        };
+        self.set(libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&info))
+    }
+
+    /// Sends an IPv6 datagram from the given source address and interface (`IPV6_PKTINFO`).
+    ///
+    /// An interface index of 0 lets the OS pick the interface based on the routing table.
+    pub fn set_source_v6(&mut self, addr: Ipv6Addr, ifindex: u32) -> &mut SendAncillary {
+        let info = libc::in6_pktinfo {
+            ipi6_addr: libc::in6_addr {
+                s6_addr: addr.octets(),
+            },
+            ipi6_ifindex: ifindex as _,
+        };
+        self.set(libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, bytes_of(&info))
+    }
+
+    /// Sets the type-of-service field of an outgoing IPv4 datagram (`IP_TOS`).
+    pub fn set_tos(&mut self, tos: u8) -> &mut SendAncillary {
+        self.set(
+            libc::IPPROTO_IP,
+            libc::IP_TOS,
+            bytes_of(&(tos as libc::c_int)),
+        )
+    }
+
+    /// Sets the time-to-live field of an outgoing IPv4 datagram (`IP_TTL`).
+    pub fn set_ttl(&mut self, ttl: u8) -> &mut SendAncillary {
+        self.set(
+            libc::IPPROTO_IP,
+            libc::IP_TTL,
+            bytes_of(&(ttl as libc::c_int)),
+        )
+    }
+
+    /// Sets the traffic class field of an outgoing IPv6 datagram (`IPV6_TCLASS`).
+    pub fn set_traffic_class(&mut self, class: u8) -> &mut SendAncillary {
+        let class = class as libc::c_int;
+        self.set(libc::IPPROTO_IPV6, libc::IPV6_TCLASS, bytes_of(&class))
+    }
+
+    /// Sets the hop limit field of an outgoing IPv6 datagram (`IPV6_HOPLIMIT`).
+    pub fn set_hop_limit(&mut self, hops: u8) -> &mut SendAncillary {
+        let hops = hops as libc::c_int;
+        self.set(libc::IPPROTO_IPV6, libc::IPV6_HOPLIMIT, bytes_of(&hops))
+    }
+
+    /// Adds a control message, replacing the previous one of the same kind.
+    fn set(&mut self, level: libc::c_int, ty: libc::c_int, data: Vec<u8>) -> &mut SendAncillary {
+        self.messages.retain(|(l, t, _)| (*l, *t) != (level, ty));
+        self.messages.push((level, ty, data));
+        self
+    }
+
+    /// Returns the control messages as `(level, type, data)` triples.
+    pub(crate) fn messages(&self) -> impl Iterator<Item = (libc::c_int, libc::c_int, &[u8])> {
+        self.messages.iter().map(|(l, t, d)| (*l, *t, &d[..]))
+    }
+}
+
+/// A buffer for ancillary data received along with a message.
+///
+/// After a successful receive, the buffer holds the control messages delivered by the OS, which
+/// can be inspected with [`RecvAncillary::messages()`].
+///
+/// # Examples
+///
+/// ```
+/// use async_io::RecvAncillary;
+///
+/// let ancillary = RecvAncillary::new();
+/// assert_eq!(ancillary.messages().count(), 0);
+/// ```
+pub struct RecvAncillary {
+    /// Storage for control messages, aligned for `cmsghdr`.
+    buf: Vec<u64>,
+
+    /// Number of bytes filled in by the last receive.
+    len: usize,
+
+    /// Whether the last receive discarded control messages that didn't fit.
+    truncated: bool,
+}
+
+impl RecvAncillary {
+    /// Creates a buffer large enough for the control messages supported by this crate.
+    pub fn new() -> RecvAncillary {
+        RecvAncillary::with_capacity(256)
+    }
+
+    /// Creates a buffer that can hold `capacity` bytes of control messages.
+    pub fn with_capacity(capacity: usize) -> RecvAncillary {
+        #[allow(clippy::manual_div_ceil)]
+        let words = (capacity + mem::size_of::<u64>() - 1) / mem::size_of::<u64>();
+        RecvAncillary {
+            buf: vec![0; words],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns `true` if control messages were discarded because the buffer was too small.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Iterates over the control messages received by the last receive.
+    pub fn messages(&self) -> Messages<'_> {
+        Messages {
+            buf: self.bytes(),
+            offset: 0,
+        }
+    }
+
+    /// Returns the received control messages as raw bytes.
+    fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len) }
+    }
+
+    /// Returns the whole buffer as a pointer and length suitable for `msg_control`.
+    pub(crate) fn as_raw(&mut self) -> (*mut libc::c_void, usize) {
+        let len = self.buf.len() * mem::size_of::<u64>();
+        (self.buf.as_mut_ptr() as *mut libc::c_void, len)
+    }
+
+    /// Records the outcome of a receive into this buffer.
+    pub(crate) fn set_received(&mut self, len: usize, truncated: bool) {
+        self.len = len;
+        self.truncated = truncated;
+    }
+}
+
+impl Default for RecvAncillary {
+    fn default() -> RecvAncillary {
+        RecvAncillary::new()
+    }
+}
+
+impl fmt::Debug for RecvAncillary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvAncillary")
+            .field("messages", &self.messages().collect::<Vec<_>>())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
+
+/// The destination address and interface of a received datagram.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PacketInfo {
+    /// The destination address from the packet header.
+    pub addr: IpAddr,
+
+    /// The index of the interface the packet was received on.
+    pub ifindex: u32,
+}
+
+/// A control message received along with a datagram.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::manual_non_exhaustive)]
+pub enum ControlMessage<'a> {
+    /// Destination address and interface (`IP_PKTINFO` or `IPV6_PKTINFO`).
+    PacketInfo(PacketInfo),
+
+    /// Type-of-service field of an IPv4 header (`IP_TOS`).
+    Tos(u8),
+
+    /// Time-to-live field of an IPv4 header (`IP_TTL`).
+    Ttl(u8),
+
+    /// Traffic class field of an IPv6 header (`IPV6_TCLASS`).
+    TrafficClass(u8),
+
+    /// Hop limit field of an IPv6 header (`IPV6_HOPLIMIT`).
+    HopLimit(u8),
+
+    /// Time at which the datagram was received (`SO_TIMESTAMPNS`).
+    Timestamp(SystemTime),
+
+    /// A control message this crate doesn't know how to parse.
+    Unknown {
+        /// The originating protocol level (`cmsg_level`).
+        level: i32,
+
+        /// The protocol-specific type (`cmsg_type`).
+        ty: i32,
+
+        /// The raw message payload.
+        data: &'a [u8],
+    },
+
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// An iterator over received control messages.
+///
+/// This struct is created by [`RecvAncillary::messages()`].
+#[derive(Debug)]
+pub struct Messages<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Messages<'a> {
+    type Item = ControlMessage<'a>;
+
+    fn next(&mut self) -> Option<ControlMessage<'a>> {
+        let (level, ty, data) = next_cmsg(self.buf, &mut self.offset)?;
+        Some(parse(level, ty, data))
+    }
+}
+
+/// Parses a raw control message.
+fn parse(level: libc::c_int, ty: libc::c_int, data: &[u8]) -> ControlMessage<'_> {
+    match (level, ty) {
+        (libc::IPPROTO_IP, libc::IP_PKTINFO)
+            if data.len() >= mem::size_of::<libc::in_pktinfo>() =>
+        {
+            let info: libc::in_pktinfo = read(data);
+            ControlMessage::PacketInfo(PacketInfo {
+                addr: Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr)).into(),
+                ifindex: info.ipi_ifindex as _,
+            })
+        }
+        (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO)
+            if data.len() >= mem::size_of::<libc::in6_pktinfo>() =>
+        {
+            let info: libc::in6_pktinfo = read(data);
+            ControlMessage::PacketInfo(PacketInfo {
+                addr: Ipv6Addr::from(info.ipi6_addr.s6_addr).into(),
+                ifindex: info.ipi6_ifindex as _,
+            })
+        }
+        (libc::IPPROTO_IP, libc::IP_TOS) if !data.is_empty() => ControlMessage::Tos(data[0]),
+        (libc::IPPROTO_IP, libc::IP_TTL) if data.len() >= mem::size_of::<libc::c_int>() => {
+            ControlMessage::Ttl(read::<libc::c_int>(data) as u8)
+        }
+        (libc::IPPROTO_IPV6, libc::IPV6_TCLASS) if data.len() >= mem::size_of::<libc::c_int>() => {
+            ControlMessage::TrafficClass(read::<libc::c_int>(data) as u8)
+        }
+        (libc::IPPROTO_IPV6, libc::IPV6_HOPLIMIT)
+            if data.len() >= mem::size_of::<libc::c_int>() =>
+        {
+            ControlMessage::HopLimit(read::<libc::c_int>(data) as u8)
+        }
+        (libc::SOL_SOCKET, libc::SO_TIMESTAMPNS)
+            if data.len() >= mem::size_of::<libc::timespec>() =>
+        {
+            let ts: libc::timespec = read(data);
+            let since_epoch = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+            ControlMessage::Timestamp(UNIX_EPOCH + since_epoch)
+        }
+        (level, ty) => ControlMessage::Unknown { level, ty, data },
+    }
+}
+
+/// Reads a value of type `T` from the beginning of a control message payload.
+fn read<T: Copy>(data: &[u8]) -> T {
+    assert!(data.len() >= mem::size_of::<T>());
+    unsafe { ptr::read_unaligned(data.as_ptr() as *const T) }
+}
@@ -0,0 +1,164 @@
+//! Batched datagram I/O with `recvmmsg()`/`sendmmsg()` and UDP segmentation offload.
+
+use std::io::{self, IoSlice, IoSliceMut};
+use std::iter;
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::ptr;
+
+use once_cell::sync::Lazy;
+use socket2::{Domain, SockAddr, Socket, Type};
+
+use crate::cmsg::{self, bytes_of};
+use crate::sys;
+
+/// The largest payload a single UDP datagram can carry over IPv4.
+pub(crate) const MAX_UDP_PAYLOAD: usize = 65507;
+
+/// The largest number of segments the kernel accepts in a single GSO send.
+pub(crate) const MAX_GSO_SEGMENTS: usize = 64;
+
+/// Receives up to `bufs.len()` datagrams, one per buffer.
+///
+/// Returns the length and source address of every received datagram.
+pub(crate) fn recv_batch(fd: RawFd, bufs: &mut [&mut [u8]]) -> io::Result<Vec<(usize, SockAddr)>> {
+    let mut iovecs: Vec<libc::iovec> = bufs
+        .iter_mut()
+        .map(|buf| libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        })
+        .collect();
+    let mut addrs: Vec<libc::sockaddr_storage> =
+        (0..bufs.len()).map(|_| unsafe { mem::zeroed() }).collect();
+
+    let mut msgs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(addrs.iter_mut())
+        .map(|(iovec, addr)| {
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            msg.msg_hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+            msg.msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as _;
+            msg.msg_hdr.msg_iov = iovec;
+            msg.msg_hdr.msg_iovlen = 1;
+            msg
+        })
+        .collect();
+
+    let n = syscall!(recvmmsg(
+        fd,
+        msgs.as_mut_ptr(),
+        msgs.len() as _,
+        0 as _,
+        ptr::null_mut(),
+    ))?;
+
+    Ok(msgs[..n as usize]
+        .iter()
+        .zip(addrs.iter())
+        .map(|(msg, addr)| {
+            let addr = unsafe {
+                SockAddr::from_raw_parts(
+                    addr as *const libc::sockaddr_storage as *const libc::sockaddr,
+                    msg.msg_hdr.msg_namelen,
+                )
+            };
+            (msg.msg_len as usize, addr)
+        })
+        .collect())
+}
+
+/// Sends a batch of datagrams, each to its own address or to the connected peer.
+///
+/// Returns the number of datagrams sent.
+pub(crate) fn send_batch(fd: RawFd, msgs: &[(&[u8], Option<SockAddr>)]) -> io::Result<usize> {
+    let mut iovecs: Vec<libc::iovec> = msgs
+        .iter()
+        .map(|(buf, _)| libc::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        })
+        .collect();
+
+    let mut hdrs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(msgs.iter())
+        .map(|(iovec, entry)| {
+            let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+            if let Some(addr) = &entry.1 {
+                msg.msg_hdr.msg_name = addr.as_ptr() as *mut libc::c_void;
+                msg.msg_hdr.msg_namelen = addr.len();
+            }
+            msg.msg_hdr.msg_iov = iovec;
+            msg.msg_hdr.msg_iovlen = 1;
+            msg
+        })
+        .collect();
+
+    let n = syscall!(sendmmsg(fd, hdrs.as_mut_ptr(), hdrs.len() as _, 0 as _))?;
+    Ok(n as usize)
+}
+
+/// Returns `true` if the kernel supports UDP generic segmentation offload (`UDP_SEGMENT`).
+pub(crate) fn gso_supported() -> bool {
+    static SUPPORTED: Lazy<bool> = Lazy::new(|| {
+        Socket::new(Domain::ipv4(), Type::dgram(), None)
+            .and_then(|socket| {
+                sys::getsockopt::<libc::c_int>(socket.as_raw_fd(), libc::SOL_UDP, libc::UDP_SEGMENT)
+            })
+            .is_ok()
+    });
+    *SUPPORTED
+}
+
+/// Sends a buffer that the kernel splits into datagrams of `segment_size` bytes.
+pub(crate) fn send_segments(
+    fd: RawFd,
+    buf: &[u8],
+    segment_size: u16,
+    addr: &SockAddr,
+) -> io::Result<usize> {
+    let size = bytes_of(&segment_size);
+    let (control, control_len) =
+        cmsg::encode(iter::once((libc::SOL_UDP, libc::UDP_SEGMENT, &size[..])));
+    cmsg::sendmsg(
+        fd,
+        &[IoSlice::new(buf)],
+        &control,
+        control_len,
+        Some(addr),
+        0,
+    )
+}
+
+/// Receives a datagram that may have been coalesced from several by generic receive offload.
+///
+/// Returns the number of bytes read, the size of the coalesced segments if reported by the
+/// kernel, and the address the datagrams came from.
+pub(crate) fn recv_coalesced(
+    fd: RawFd,
+    buf: &mut [u8],
+) -> io::Result<(usize, Option<usize>, SockAddr)> {
+    let mut control = [0u64; 8];
+    let control_len = mem::size_of_val(&control);
+    let received = cmsg::recvmsg(
+        fd,
+        &mut [IoSliceMut::new(buf)],
+        (control.as_mut_ptr() as *mut libc::c_void, control_len),
+        0,
+    )?;
+
+    let control =
+        unsafe { std::slice::from_raw_parts(control.as_ptr() as *const u8, received.control_len) };
+    let mut offset = 0;
+    let mut segment_size = None;
+    while let Some((level, ty, data)) = cmsg::next_cmsg(control, &mut offset) {
+        if (level, ty) == (libc::SOL_UDP, libc::UDP_GRO)
+            && data.len() >= mem::size_of::<libc::c_int>()
+        {
+            let size = unsafe { ptr::read_unaligned(data.as_ptr() as *const libc::c_int) };
+            segment_size = Some(size as usize);
+        }
+    }
+    Ok((received.len, segment_size, received.addr))
+}
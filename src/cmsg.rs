@@ -0,0 +1,220 @@
+//! Raw `sendmsg()`/`recvmsg()` calls and encoding of control messages.
+
+use std::fs::File;
+use std::io::{self, IoSlice, IoSliceMut};
+use std::iter;
+use std::mem;
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::ptr;
+
+use socket2::SockAddr;
+
+/// Returns the raw bytes of a plain C struct.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn bytes_of<T: Copy>(value: &T) -> Vec<u8> {
+    let ptr = value as *const T as *const u8;
+    unsafe { std::slice::from_raw_parts(ptr, mem::size_of::<T>()) }.to_vec()
+}
+
+/// Encodes `(level, type, data)` triples into an aligned control message buffer.
+///
+/// Returns the buffer and the number of bytes to pass as `msg_controllen`.
+pub(crate) fn encode<'a>(
+    messages: impl Iterator<Item = (libc::c_int, libc::c_int, &'a [u8])>,
+) -> (Vec<u64>, usize) {
+    let messages: Vec<_> = messages.collect();
+    let len: usize = messages
+        .iter()
+        .map(|(_, _, data)| unsafe { libc::CMSG_SPACE(data.len() as _) } as usize)
+        .sum();
+    #[allow(clippy::manual_div_ceil)]
+    let mut buf = vec![0u64; (len + mem::size_of::<u64>() - 1) / mem::size_of::<u64>()];
+
+    let base = buf.as_mut_ptr() as *mut u8;
+    let mut offset = 0;
+    for (level, ty, data) in messages {
+        unsafe {
+            let hdr = base.add(offset) as *mut libc::cmsghdr;
+            (*hdr).cmsg_level = level;
+            (*hdr).cmsg_type = ty;
+            (*hdr).cmsg_len = libc::CMSG_LEN(data.len() as _) as _;
+            let payload = libc::CMSG_DATA(hdr);
+            ptr::copy_nonoverlapping(data.as_ptr(), payload, data.len());
+            offset += libc::CMSG_SPACE(data.len() as _) as usize;
+        }
+    }
+    (buf, len)
+}
+
+/// Sends a message with ancillary data, optionally to the specified address.
+pub(crate) fn sendmsg(
+    fd: RawFd,
+    bufs: &[IoSlice<'_>],
+    control: &[u64],
+    control_len: usize,
+    addr: Option<&SockAddr>,
+    flags: libc::c_int,
+) -> io::Result<usize> {
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    if let Some(addr) = addr {
+        msg.msg_name = addr.as_ptr() as *mut libc::c_void;
+        msg.msg_namelen = addr.len();
+    }
+    msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = bufs.len() as _;
+    if control_len > 0 {
+        msg.msg_control = control.as_ptr() as *mut libc::c_void;
+        msg.msg_controllen = control_len as _;
+    }
+    let n = syscall!(sendmsg(fd, &msg, flags))?;
+    Ok(n as usize)
+}
+
+/// The outcome of a successful `recvmsg()` call.
+pub(crate) struct Received {
+    /// Number of bytes received.
+    pub(crate) len: usize,
+
+    /// Address the message came from.
+    pub(crate) addr: SockAddr,
+
+    /// Number of bytes of control messages received.
+    pub(crate) control_len: usize,
+
+    /// Flags describing the received message, e.g. `MSG_CTRUNC`.
+    pub(crate) flags: libc::c_int,
+}
+
+/// Receives a message with ancillary data into the given control buffer.
+pub(crate) fn recvmsg(
+    fd: RawFd,
+    bufs: &mut [IoSliceMut<'_>],
+    control: (*mut libc::c_void, usize),
+    flags: libc::c_int,
+) -> io::Result<Received> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = &mut storage as *mut libc::sockaddr_storage as *mut libc::c_void;
+    msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = bufs.as_mut_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = bufs.len() as _;
+    if control.1 > 0 {
+        msg.msg_control = control.0;
+        msg.msg_controllen = control.1 as _;
+    }
+
+    let n = syscall!(recvmsg(fd, &mut msg, flags))?;
+    let addr = unsafe {
+        SockAddr::from_raw_parts(
+            &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+            msg.msg_namelen,
+        )
+    };
+    Ok(Received {
+        len: n as usize,
+        addr,
+        control_len: msg.msg_controllen as usize,
+        flags: msg.msg_flags,
+    })
+}
+
+/// Reads the control message at `offset` and advances `offset` past it.
+pub(crate) fn next_cmsg<'a>(
+    buf: &'a [u8],
+    offset: &mut usize,
+) -> Option<(libc::c_int, libc::c_int, &'a [u8])> {
+    let header_len = unsafe { libc::CMSG_LEN(0) } as usize;
+    if buf.len().saturating_sub(*offset) < mem::size_of::<libc::cmsghdr>() {
+        return None;
+    }
+
+    let hdr = unsafe { ptr::read_unaligned(buf[*offset..].as_ptr() as *const libc::cmsghdr) };
+    let cmsg_len = hdr.cmsg_len as usize;
+    if cmsg_len < header_len || *offset + cmsg_len > buf.len() {
+        return None;
+    }
+
+    let data = &buf[*offset + header_len..*offset + cmsg_len];
+    *offset += unsafe { libc::CMSG_SPACE(data.len() as _) } as usize;
+    Some((hdr.cmsg_level, hdr.cmsg_type, data))
+}
+
+/// Flags for sending on a socket without raising `SIGPIPE` where the OS supports it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) const SEND_FLAGS: libc::c_int = 0;
+
+/// Sends data along with file descriptors (`SCM_RIGHTS`) on a connected Unix socket.
+pub(crate) fn send_fds(fd: RawFd, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    let data =
+        unsafe { std::slice::from_raw_parts(fds.as_ptr() as *const u8, mem::size_of_val(fds)) };
+
+    let (control, control_len) = if fds.is_empty() {
+        (Vec::new(), 0)
+    } else {
+        encode(iter::once((libc::SOL_SOCKET, libc::SCM_RIGHTS, data)))
+    };
+    sendmsg(
+        fd,
+        &[IoSlice::new(buf)],
+        &control,
+        control_len,
+        None,
+        SEND_FLAGS,
+    )
+}
+
+/// Receives data along with up to `max_fds` file descriptors (`SCM_RIGHTS`).
+///
+/// Received file descriptors have the close-on-exec flag set. If the sender passed more than
+/// `max_fds` file descriptors, the extra ones are closed.
+pub(crate) fn recv_fds(
+    fd: RawFd,
+    buf: &mut [u8],
+    max_fds: usize,
+) -> io::Result<(usize, Vec<File>)> {
+    let space = unsafe { libc::CMSG_SPACE((max_fds * mem::size_of::<RawFd>()) as _) } as usize;
+    #[allow(clippy::manual_div_ceil)]
+    let mut control = vec![0u64; (space + mem::size_of::<u64>() - 1) / mem::size_of::<u64>()];
+
+    // Atomically set close-on-exec on platforms that support it.
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    let flags = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    let flags = 0;
+
+    let received = recvmsg(
+        fd,
+        &mut [IoSliceMut::new(buf)],
+        (control.as_mut_ptr() as *mut libc::c_void, space),
+        flags,
+    )?;
+
+    // Take ownership of all received file descriptors so that they get closed on error.
+    let control =
+        unsafe { std::slice::from_raw_parts(control.as_ptr() as *const u8, received.control_len) };
+    let mut fds = Vec::new();
+    let mut offset = 0;
+    while let Some((level, ty, data)) = next_cmsg(control, &mut offset) {
+        if (level, ty) == (libc::SOL_SOCKET, libc::SCM_RIGHTS) {
+            for raw in data.chunks_exact(mem::size_of::<RawFd>()) {
+                let raw = unsafe { ptr::read_unaligned(raw.as_ptr() as *const RawFd) };
+                fds.push(unsafe { File::from_raw_fd(raw) });
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    for fd in &fds {
+        syscall!(fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC))?;
+    }
+
+    // The data has been consumed, so it's returned even if the sender passed too many file
+    // descriptors. `CMSG_SPACE()` rounds up for alignment, which may leave room for more than
+    // `max_fds` of them, so close the extra ones.
+    fds.truncate(max_fds);
+    Ok((received.len, fds))
+}
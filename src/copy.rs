@@ -0,0 +1,247 @@
+//! Copying between file descriptors with `sendfile()` and `splice()`.
+//!
+//! On Linux, data is moved inside the kernel without passing through userspace buffers. On
+//! other platforms, or when a file descriptor doesn't support zero-copy transfers, data is copied
+//! with `read()` and `write()` instead.
+
+#[cfg(target_os = "linux")]
+use std::fs::File;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::FromRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use futures_lite::future;
+
+use crate::{cmsg, sys, Async};
+
+/// The largest number of bytes moved by a single system call.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Sends up to `count` bytes of a file starting at `offset` into a socket.
+///
+/// Returns the number of bytes sent, which is 0 at the end of the file.
+#[cfg(target_os = "linux")]
+pub(crate) fn sendfile(socket: RawFd, file: RawFd, offset: u64, count: usize) -> io::Result<usize> {
+    let mut offset = offset as libc::off64_t;
+    let count = count.min(CHUNK_SIZE);
+    let n = syscall!(sendfile64(socket, file, &mut offset, count))?;
+    Ok(n as usize)
+}
+
+/// Reads up to `count` bytes of a file starting at `offset` and writes them into a socket.
+///
+/// Returns the number of bytes written, which is 0 at the end of the file.
+pub(crate) fn pread_write(
+    socket: RawFd,
+    file: RawFd,
+    offset: u64,
+    count: usize,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    let count = count.min(buf.len());
+    let n = syscall!(pread(
+        file,
+        buf.as_mut_ptr() as *mut libc::c_void,
+        count,
+        offset as libc::off_t,
+    ))?;
+    if n == 0 {
+        return Ok(0);
+    }
+    write(socket, &buf[..n as usize])
+}
+
+/// Returns `true` if an error means the file descriptors don't support zero-copy transfers.
+#[cfg(target_os = "linux")]
+#[allow(clippy::match_like_matches_macro)]
+pub(crate) fn is_unsupported(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(libc::EINVAL) | Some(libc::ENOSYS) | Some(libc::EOPNOTSUPP) => true,
+        _ => false,
+    }
+}
+
+/// Copies data in both directions between two handles until both reach end of stream.
+///
+/// When one direction reaches end of stream, the write half of its destination is shut down if
+/// it is a socket, so that the peer observes end of stream too. Returns the number of bytes
+/// copied from `a` to `b` and from `b` to `a`.
+///
+/// On Linux, data is moved with `splice()` through a pipe without copying it into userspace.
+/// Elsewhere, or if a handle doesn't support `splice()`, data is copied with `read()` and
+/// `write()`. Between partial transfers, the copy waits for the handles to become readable or
+/// writable.
+///
+/// Writing into a socket whose peer has reset the connection raises `SIGPIPE`, except for
+/// `write()` on Linux and Android, which fails with an error instead. `splice()` can't suppress
+/// the signal, so the process should ignore `SIGPIPE`, as Rust programs do by default.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io::{copy_bidirectional, Async};
+/// use std::net::{TcpListener, TcpStream};
+///
+/// # blocking::block_on(async {
+/// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
+/// let (client, _) = listener.accept().await?;
+/// let server = Async::<TcpStream>::connect(([127, 0, 0, 1], 9000)).await?;
+///
+/// let (sent, received) = copy_bidirectional(&client, &server).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub async fn copy_bidirectional<A: AsRawFd, B: AsRawFd>(
+    a: &Async<A>,
+    b: &Async<B>,
+) -> io::Result<(u64, u64)> {
+    future::try_join(copy(a, b), copy(b, a)).await
+}
+
+/// Copies data from one handle to another until end of stream and then shuts down writing.
+async fn copy<R: AsRawFd, W: AsRawFd>(reader: &Async<R>, writer: &Async<W>) -> io::Result<u64> {
+    #[cfg(target_os = "linux")]
+    let copied = splice_all(reader, writer).await?;
+    #[cfg(not(target_os = "linux"))]
+    let copied = read_write_all(reader, writer).await?;
+
+    sys::shutdown_write(writer.source.raw)?;
+    Ok(copied)
+}
+
+/// Moves data from one handle to another through a pipe until end of stream.
+///
+/// If either handle doesn't support `splice()`, data left in the pipe is written out and the
+/// rest is copied with `read()` and `write()`.
+#[cfg(target_os = "linux")]
+async fn splice_all<R: AsRawFd, W: AsRawFd>(
+    reader: &Async<R>,
+    writer: &Async<W>,
+) -> io::Result<u64> {
+    let (pipe_r, pipe_w) = pipe()?;
+    let flags = libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK;
+    let mut copied = 0;
+
+    loop {
+        // The pipe is empty, so "would block" means the reader has no data yet.
+        let res = reader
+            .read_with(|io| {
+                let n = syscall!(splice(
+                    io.as_raw_fd(),
+                    std::ptr::null_mut(),
+                    pipe_w.as_raw_fd(),
+                    std::ptr::null_mut(),
+                    CHUNK_SIZE,
+                    flags,
+                ))?;
+                Ok(n as usize)
+            })
+            .await;
+        let mut pending = match res {
+            Err(err) if is_unsupported(&err) => {
+                return Ok(copied + read_write_all(reader, writer).await?);
+            }
+            Ok(0) => return Ok(copied),
+            res => res?,
+        };
+
+        // The pipe holds data, so "would block" means the writer is full.
+        while pending > 0 {
+            let res = writer
+                .write_with(|io| {
+                    let n = syscall!(splice(
+                        pipe_r.as_raw_fd(),
+                        std::ptr::null_mut(),
+                        io.as_raw_fd(),
+                        std::ptr::null_mut(),
+                        pending,
+                        flags,
+                    ))?;
+                    Ok(n as usize)
+                })
+                .await;
+            let n = match res {
+                Err(err) if is_unsupported(&err) => {
+                    // Write out what is still in the pipe before copying the rest.
+                    let mut buf = vec![0u8; pending];
+                    let mut drained = 0;
+                    while drained < pending {
+                        let n = read(pipe_r.as_raw_fd(), &mut buf[drained..])?;
+                        write_all(writer, &buf[drained..drained + n]).await?;
+                        drained += n;
+                    }
+                    copied += pending as u64;
+                    return Ok(copied + read_write_all(reader, writer).await?);
+                }
+                res => res?,
+            };
+            pending -= n;
+            copied += n as u64;
+        }
+    }
+}
+
+/// Copies data from one handle to another through a userspace buffer until end of stream.
+async fn read_write_all<R: AsRawFd, W: AsRawFd>(
+    reader: &Async<R>,
+    writer: &Async<W>,
+) -> io::Result<u64> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut copied = 0;
+
+    loop {
+        let len = reader
+            .read_with(|io| read(io.as_raw_fd(), &mut buf))
+            .await?;
+        if len == 0 {
+            return Ok(copied);
+        }
+
+        write_all(writer, &buf[..len]).await?;
+        copied += len as u64;
+    }
+}
+
+/// Writes a whole buffer into a handle.
+async fn write_all<W: AsRawFd>(writer: &Async<W>, buf: &[u8]) -> io::Result<()> {
+    let mut written = 0;
+    while written < buf.len() {
+        let n = writer
+            .write_with(|io| write(io.as_raw_fd(), &buf[written..]))
+            .await?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        written += n;
+    }
+    Ok(())
+}
+
+/// Creates a non-blocking pipe, returning its read and write ends.
+#[cfg(target_os = "linux")]
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    syscall!(pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK))?;
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+/// Reads from a file descriptor.
+fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+    let n = syscall!(read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()))?;
+    Ok(n as usize)
+}
+
+/// Writes into a file descriptor.
+///
+/// Sockets are written with `send()` so that a reset connection returns an error instead of
+/// raising `SIGPIPE` where the OS supports it.
+fn write(fd: RawFd, buf: &[u8]) -> io::Result<usize> {
+    let ptr = buf.as_ptr() as *const libc::c_void;
+    let n = match syscall!(send(fd, ptr, buf.len(), cmsg::SEND_FLAGS)) {
+        Err(err) if err.raw_os_error() == Some(libc::ENOTSOCK) => {
+            syscall!(write(fd, ptr, buf.len()))?
+        }
+        res => res?,
+    };
+    Ok(n as usize)
+}
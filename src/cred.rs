@@ -0,0 +1,156 @@
+//! Credentials of processes on the other end of a Unix socket.
+
+use std::io;
+use std::os::unix::io::RawFd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::{
+    io::{IoSlice, IoSliceMut},
+    iter, mem, ptr,
+};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::cmsg;
+
+/// Credentials of a process: user ID, group ID, and process ID.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::UCred;
+///
+/// let cred = UCred::current();
+/// assert_eq!(cred.pid, Some(std::process::id() as i32));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UCred {
+    /// The effective user ID.
+    pub uid: u32,
+
+    /// The effective group ID.
+    pub gid: u32,
+
+    /// The process ID, if the OS reports it.
+    pub pid: Option<i32>,
+}
+
+impl UCred {
+    /// Returns the credentials of the current process.
+    pub fn current() -> UCred {
+        unsafe {
+            UCred {
+                uid: libc::geteuid(),
+                gid: libc::getegid(),
+                pid: Some(libc::getpid()),
+            }
+        }
+    }
+}
+
+/// Returns the credentials of the peer of a connected Unix stream socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let cred: libc::ucred = crate::sys::getsockopt(fd, libc::SOL_SOCKET, libc::SO_PEERCRED)?;
+    Ok(UCred {
+        uid: cred.uid,
+        gid: cred.gid,
+        pid: Some(cred.pid),
+    })
+}
+
+/// Returns the credentials of the peer of a connected Unix stream socket.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+))]
+pub(crate) fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    syscall!(getpeereid(fd, &mut uid, &mut gid))?;
+    Ok(UCred {
+        uid,
+        gid,
+        pid: None,
+    })
+}
+
+/// Returns the credentials of the peer of a connected Unix stream socket.
+#[cfg(target_os = "illumos")]
+pub(crate) fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut ucred: *mut libc::ucred_t = std::ptr::null_mut();
+    syscall!(getpeerucred(fd, &mut ucred))?;
+    let cred = unsafe {
+        UCred {
+            uid: libc::ucred_geteuid(ucred),
+            gid: libc::ucred_getegid(ucred),
+            pid: match libc::ucred_getpid(ucred) {
+                -1 => None,
+                pid => Some(pid),
+            },
+        }
+    };
+    unsafe { libc::ucred_free(ucred) };
+    Ok(cred)
+}
+
+/// Sends data along with credentials (`SCM_CREDENTIALS`) on a connected Unix socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn send_cred(fd: RawFd, buf: &[u8], cred: &UCred) -> io::Result<usize> {
+    let cred = libc::ucred {
+        pid: cred.pid.unwrap_or_else(|| unsafe { libc::getpid() }),
+        uid: cred.uid,
+        gid: cred.gid,
+    };
+    let data = cmsg::bytes_of(&cred);
+    let (control, control_len) = cmsg::encode(iter::once((
+        libc::SOL_SOCKET,
+        libc::SCM_CREDENTIALS,
+        &data[..],
+    )));
+    cmsg::sendmsg(
+        fd,
+        &[IoSlice::new(buf)],
+        &control,
+        control_len,
+        None,
+        libc::MSG_NOSIGNAL,
+    )
+}
+
+/// Receives data along with the sender's credentials (`SCM_CREDENTIALS`).
+///
+/// Credentials are only attached if `SO_PASSCRED` is enabled on the receiving socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn recv_cred(fd: RawFd, buf: &mut [u8]) -> io::Result<(usize, Option<UCred>)> {
+    let mut control = [0u64; 8];
+    let received = cmsg::recvmsg(
+        fd,
+        &mut [IoSliceMut::new(buf)],
+        (
+            control.as_mut_ptr() as *mut libc::c_void,
+            mem::size_of_val(&control),
+        ),
+        0,
+    )?;
+
+    let control =
+        unsafe { std::slice::from_raw_parts(control.as_ptr() as *const u8, received.control_len) };
+    let mut offset = 0;
+    let mut cred = None;
+    while let Some((level, ty, data)) = cmsg::next_cmsg(control, &mut offset) {
+        if (level, ty) == (libc::SOL_SOCKET, libc::SCM_CREDENTIALS)
+            && data.len() >= mem::size_of::<libc::ucred>()
+        {
+            let raw = unsafe { ptr::read_unaligned(data.as_ptr() as *const libc::ucred) };
+            cred = Some(UCred {
+                uid: raw.uid,
+                gid: raw.gid,
+                pid: Some(raw.pid),
+            });
+        }
+    }
+    Ok((received.len, cred))
+}
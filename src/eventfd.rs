@@ -0,0 +1,89 @@
+//! Event counters with `eventfd`.
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+/// An event counter for notifications between threads or processes.
+///
+/// In counter mode, every read returns the current value of the counter and resets it to zero.
+/// In semaphore mode, every read returns 1 and decrements the counter by one. Reads block while
+/// the counter is zero.
+///
+/// Use [`Async<EventFd>`][`crate::Async`] to wait for notifications asynchronously.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::eventfd::EventFd;
+///
+/// let event = EventFd::new(0)?;
+/// event.notify(3)?;
+/// assert_eq!(event.read()?, 3);
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct EventFd(File);
+
+impl EventFd {
+    /// Creates an event counter in counter mode with an initial value.
+    pub fn new(initial: u32) -> io::Result<EventFd> {
+        EventFd::with_flags(initial, 0)
+    }
+
+    /// Creates an event counter in semaphore mode with an initial value.
+    pub fn semaphore(initial: u32) -> io::Result<EventFd> {
+        EventFd::with_flags(initial, libc::EFD_SEMAPHORE)
+    }
+
+    fn with_flags(initial: u32, flags: libc::c_int) -> io::Result<EventFd> {
+        let fd = syscall!(eventfd(initial, libc::EFD_CLOEXEC | flags))?;
+        Ok(EventFd(unsafe { File::from_raw_fd(fd) }))
+    }
+
+    /// Adds `n` to the counter.
+    ///
+    /// If the counter would exceed `u64::MAX - 1`, this blocks, or fails with
+    /// [`io::ErrorKind::WouldBlock`] in non-blocking mode. Adding `u64::MAX` is an error.
+    pub fn notify(&self, n: u64) -> io::Result<()> {
+        let buf = n.to_ne_bytes();
+        syscall!(write(
+            self.0.as_raw_fd(),
+            buf.as_ptr() as *const libc::c_void,
+            buf.len()
+        ))?;
+        Ok(())
+    }
+
+    /// Reads the counter.
+    ///
+    /// In counter mode, returns its value and resets it to zero. In semaphore mode, returns 1 and
+    /// decrements it by one.
+    pub fn read(&self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        syscall!(read(
+            self.0.as_raw_fd(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len()
+        ))?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for EventFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> EventFd {
+        EventFd(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for EventFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
@@ -0,0 +1,459 @@
+//! I/O handles served by a thread pool when the reactor doesn't support them.
+//!
+//! Regular files and directories can't be waited on by the reactor, so
+//! [`Async::new_blocking_fallback()`] hands them to a small pool of threads that perform the
+//! blocking reads and writes instead.
+
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use futures_lite::*;
+use once_cell::sync::Lazy;
+
+use crate::Async;
+
+/// The size of the buffer used by a single read or write on the thread pool.
+const BUF_SIZE: usize = 8 * 1024;
+
+/// The number of threads in the pool.
+const THREADS: usize = 4;
+
+/// An async I/O handle that is either registered in the reactor or served by a thread pool.
+///
+/// Created by [`Async::new_blocking_fallback()`]. Both kinds implement [`AsyncRead`],
+/// [`AsyncWrite`], and [`AsyncSeek`] in the same way, except that on the thread pool a write
+/// returns before it completes. Operations on the thread pool run one at a time in order, so a
+/// read or seek waits for earlier writes, and [`flush()`][`futures_lite::AsyncWriteExt::flush()`]
+/// waits for all of them to complete.
+pub struct Fallback<T> {
+    inner: Inner<T>,
+}
+
+enum Inner<T> {
+    Reactor(Async<T>),
+    Pool(Unblock<T>),
+}
+
+impl<T> Fallback<T> {
+    pub(crate) fn reactor(io: Async<T>) -> Fallback<T> {
+        Fallback {
+            inner: Inner::Reactor(io),
+        }
+    }
+
+    pub(crate) fn pool(io: T) -> Fallback<T> {
+        Fallback {
+            inner: Inner::Pool(Unblock::new(io)),
+        }
+    }
+
+    /// Gets a reference to the inner I/O handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::fs::File;
+    ///
+    /// # blocking::block_on(async {
+    /// let file = Async::new_blocking_fallback(File::open("Cargo.toml")?)?;
+    /// let metadata = file.get_ref().metadata()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn get_ref(&self) -> &T {
+        match &self.inner {
+            Inner::Reactor(io) => io.get_ref(),
+            Inner::Pool(unblock) => unblock.get_ref(),
+        }
+    }
+
+    /// Returns `true` if the handle is served by the thread pool rather than the reactor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::fs::File;
+    ///
+    /// # blocking::block_on(async {
+    /// let file = Async::new_blocking_fallback(File::open("Cargo.toml")?)?;
+    ///
+    /// // Epoll doesn't support regular files, but kqueue does.
+    /// if cfg!(any(target_os = "linux", target_os = "android")) {
+    ///     assert!(file.is_blocking());
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn is_blocking(&self) -> bool {
+        match &self.inner {
+            Inner::Reactor(_) => false,
+            Inner::Pool(_) => true,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Fallback<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Inner::Reactor(io) => f.debug_tuple("Reactor").field(io.get_ref()).finish(),
+            Inner::Pool(unblock) => f.debug_tuple("Pool").field(unblock.get_ref()).finish(),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncRead for Fallback<T>
+where
+    for<'a> &'a T: Read,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.inner {
+            Inner::Reactor(io) => Pin::new(&mut &*io).poll_read(cx, buf),
+            Inner::Pool(unblock) => unblock.poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncWrite for Fallback<T>
+where
+    for<'a> &'a T: Write,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.inner {
+            Inner::Reactor(io) => Pin::new(&mut &*io).poll_write(cx, buf),
+            Inner::Pool(unblock) => unblock.poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.inner {
+            Inner::Reactor(io) => Pin::new(&mut &*io).poll_flush(cx),
+            Inner::Pool(unblock) => unblock.poll_flush(cx),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.inner {
+            Inner::Reactor(io) => Pin::new(&mut &*io).poll_close(cx),
+            Inner::Pool(unblock) => unblock.poll_flush(cx),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> AsyncSeek for Fallback<T>
+where
+    for<'a> &'a T: Seek,
+{
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        match &mut self.inner {
+            Inner::Reactor(io) => Poll::Ready(io.get_ref().seek(pos)),
+            Inner::Pool(unblock) => unblock.poll_seek(cx, pos),
+        }
+    }
+}
+
+/// An I/O handle whose operations run on the thread pool one at a time.
+pub(crate) struct Unblock<T> {
+    io: Arc<T>,
+
+    /// Data read on the thread pool.
+    buf: Vec<u8>,
+
+    /// How much of the read data has been consumed.
+    pos: usize,
+
+    /// The operation in flight.
+    state: State,
+}
+
+/// An operation on the thread pool.
+enum State {
+    /// No operation is in flight.
+    Idle,
+
+    /// A read, which returns its buffer.
+    Reading(Task<(Vec<u8>, io::Result<usize>)>),
+
+    /// A write.
+    Writing(Task<io::Result<()>>),
+
+    /// A flush.
+    Flushing(Task<io::Result<()>>),
+
+    /// A seek to the requested position.
+    Seeking(SeekFrom, Task<io::Result<u64>>),
+}
+
+impl<T> Unblock<T> {
+    pub(crate) fn new(io: T) -> Unblock<T> {
+        Unblock {
+            io: Arc::new(io),
+            buf: Vec::new(),
+            pos: 0,
+            state: State::Idle,
+        }
+    }
+
+    /// Gets a reference to the inner I/O handle.
+    pub(crate) fn get_ref(&self) -> &T {
+        &self.io
+    }
+}
+
+impl<T: Send + Sync + 'static> Unblock<T> {
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>
+    where
+        for<'a> &'a T: Read,
+    {
+        loop {
+            if self.pos < self.buf.len() {
+                let n = buf.len().min(self.buf.len() - self.pos);
+                buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+                self.pos += n;
+                return Poll::Ready(Ok(n));
+            }
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+
+            match &mut self.state {
+                State::Idle => {
+                    let io = self.io.clone();
+                    let mut data = Vec::new();
+                    mem::swap(&mut data, &mut self.buf);
+                    data.resize(buf.len().min(BUF_SIZE), 0);
+                    self.state = State::Reading(spawn(move || {
+                        let res = (&*io).read(&mut data);
+                        (data, res)
+                    }));
+                }
+                State::Reading(task) => {
+                    let (data, res) = ready!(Pin::new(task).poll(cx));
+                    self.state = State::Idle;
+                    self.buffer(data, res)?;
+                    if self.buf.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                }
+                _ => ready!(self.poll_other(cx))?,
+            }
+        }
+    }
+
+    pub(crate) fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+    where
+        for<'a> &'a T: Write,
+    {
+        loop {
+            match &mut self.state {
+                State::Idle => {
+                    if buf.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    let io = self.io.clone();
+                    let data = buf[..buf.len().min(BUF_SIZE)].to_vec();
+                    let n = data.len();
+                    self.state = State::Writing(spawn(move || (&*io).write_all(&data)));
+                    return Poll::Ready(Ok(n));
+                }
+                _ => ready!(self.poll_other(cx))?,
+            }
+        }
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    where
+        for<'a> &'a T: Write,
+    {
+        loop {
+            match &mut self.state {
+                State::Idle => {
+                    let io = self.io.clone();
+                    self.state = State::Flushing(spawn(move || (&*io).flush()));
+                }
+                State::Flushing(task) => {
+                    let res = ready!(Pin::new(task).poll(cx));
+                    self.state = State::Idle;
+                    return Poll::Ready(res);
+                }
+                _ => ready!(self.poll_other(cx))?,
+            }
+        }
+    }
+
+    pub(crate) fn poll_seek(&mut self, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<io::Result<u64>>
+    where
+        for<'a> &'a T: Seek,
+    {
+        loop {
+            match &mut self.state {
+                State::Idle => {
+                    // Read-ahead data hasn't been consumed yet, so the file offset is past the
+                    // logical position.
+                    let target = match pos {
+                        SeekFrom::Current(n) => {
+                            SeekFrom::Current(n - (self.buf.len() - self.pos) as i64)
+                        }
+                        pos => pos,
+                    };
+                    self.buf.clear();
+                    self.pos = 0;
+
+                    let io = self.io.clone();
+                    self.state = State::Seeking(pos, spawn(move || (&*io).seek(target)));
+                }
+                State::Seeking(requested, task) if *requested == pos => {
+                    let res = ready!(Pin::new(task).poll(cx));
+                    self.state = State::Idle;
+                    return Poll::Ready(res);
+                }
+                _ => ready!(self.poll_other(cx))?,
+            }
+        }
+    }
+
+    /// Waits for the operation in flight to complete and leaves the handle idle.
+    ///
+    /// Read data is kept for the next read. Errors from writes, flushes, and seeks are returned.
+    fn poll_other(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let res = match &mut self.state {
+            State::Idle => Ok(()),
+            State::Reading(task) => {
+                let (data, res) = ready!(Pin::new(task).poll(cx));
+                self.state = State::Idle;
+                return Poll::Ready(self.buffer(data, res));
+            }
+            State::Writing(task) | State::Flushing(task) => ready!(Pin::new(task).poll(cx)),
+            State::Seeking(_, task) => ready!(Pin::new(task).poll(cx)).map(drop),
+        };
+        self.state = State::Idle;
+        Poll::Ready(res)
+    }
+
+    /// Stores the outcome of a read as data for the next reads.
+    fn buffer(&mut self, data: Vec<u8>, res: io::Result<usize>) -> io::Result<()> {
+        self.buf = data;
+        self.pos = 0;
+        match res {
+            Ok(n) => {
+                self.buf.truncate(n);
+                Ok(())
+            }
+            Err(err) => {
+                self.buf.clear();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// A job for the thread pool.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The thread pool, whose threads are started on first use and take jobs from a shared queue.
+///
+/// Operations on a handle run one at a time, so a few threads are enough even while one of them
+/// is blocked reading from standard input.
+static POOL: Lazy<Mutex<mpsc::Sender<Job>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for _ in 0..THREADS {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name("async-io-fallback".to_string())
+            .spawn(move || loop {
+                let job = receiver.lock().unwrap().recv();
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            })
+            .expect("cannot spawn fallback thread");
+    }
+
+    Mutex::new(sender)
+});
+
+/// Runs a closure on the thread pool and returns a future for its result.
+fn spawn<R, F>(f: F) -> Task<R>
+where
+    R: Send + 'static,
+    F: FnOnce() -> R + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(TaskState {
+        result: None,
+        wakers: Vec::new(),
+    }));
+
+    let job: Job = Box::new({
+        let shared = shared.clone();
+        move || {
+            let result = f();
+            let mut state = shared.lock().unwrap();
+            state.result = Some(result);
+            let wakers: Vec<Waker> = state.wakers.drain(..).collect();
+            drop(state);
+
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    });
+    POOL.lock()
+        .unwrap()
+        .send(job)
+        .expect("fallback threads have exited");
+
+    Task(shared)
+}
+
+/// The result of a closure running on the thread pool.
+struct Task<R>(Arc<Mutex<TaskState<R>>>);
+
+struct TaskState<R> {
+    result: Option<R>,
+
+    /// Tasks waiting for the result.
+    ///
+    /// A handle shared by several tasks, e.g. standard output, may be polled by each of them.
+    wakers: Vec<Waker>,
+}
+
+impl<R> Future for Task<R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let mut state = self.0.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Filesystem change notifications with `inotify`.
+
+use std::collections::VecDeque;
+use std::ffi::{CString, OsStr, OsString};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::ops::{BitOr, BitOrAssign};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::Path;
+use std::ptr;
+
+/// A set of filesystem events.
+///
+/// Used both for choosing the events a watch reports and for describing a reported event.
+/// Masks can be combined with the `|` operator.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::inotify::WatchMask;
+///
+/// let mask = WatchMask::CREATE | WatchMask::DELETE;
+/// assert!(mask.contains(WatchMask::CREATE));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchMask(u32);
+
+impl WatchMask {
+    /// A file was accessed.
+    pub const ACCESS: WatchMask = WatchMask(libc::IN_ACCESS);
+
+    /// A file was modified.
+    pub const MODIFY: WatchMask = WatchMask(libc::IN_MODIFY);
+
+    /// Metadata changed.
+    pub const ATTRIB: WatchMask = WatchMask(libc::IN_ATTRIB);
+
+    /// A file opened for writing was closed.
+    pub const CLOSE_WRITE: WatchMask = WatchMask(libc::IN_CLOSE_WRITE);
+
+    /// A file not opened for writing was closed.
+    pub const CLOSE_NOWRITE: WatchMask = WatchMask(libc::IN_CLOSE_NOWRITE);
+
+    /// A file was opened.
+    pub const OPEN: WatchMask = WatchMask(libc::IN_OPEN);
+
+    /// A file was moved out of a watched directory.
+    pub const MOVED_FROM: WatchMask = WatchMask(libc::IN_MOVED_FROM);
+
+    /// A file was moved into a watched directory.
+    pub const MOVED_TO: WatchMask = WatchMask(libc::IN_MOVED_TO);
+
+    /// A file was created in a watched directory.
+    pub const CREATE: WatchMask = WatchMask(libc::IN_CREATE);
+
+    /// A file was deleted from a watched directory.
+    pub const DELETE: WatchMask = WatchMask(libc::IN_DELETE);
+
+    /// The watched file or directory was deleted.
+    pub const DELETE_SELF: WatchMask = WatchMask(libc::IN_DELETE_SELF);
+
+    /// The watched file or directory was moved.
+    pub const MOVE_SELF: WatchMask = WatchMask(libc::IN_MOVE_SELF);
+
+    /// All of the events above.
+    pub const ALL_EVENTS: WatchMask = WatchMask(libc::IN_ALL_EVENTS);
+
+    /// Reported: the filesystem containing the watched object was unmounted.
+    pub const UNMOUNT: WatchMask = WatchMask(libc::IN_UNMOUNT);
+
+    /// Reported: the event queue overflowed and events were lost.
+    pub const Q_OVERFLOW: WatchMask = WatchMask(libc::IN_Q_OVERFLOW);
+
+    /// Reported: the watch was removed.
+    pub const IGNORED: WatchMask = WatchMask(libc::IN_IGNORED);
+
+    /// Reported: the subject of the event is a directory.
+    pub const ISDIR: WatchMask = WatchMask(libc::IN_ISDIR);
+
+    /// Creates a mask from raw `IN_*` bits.
+    pub fn from_bits(bits: u32) -> WatchMask {
+        WatchMask(bits)
+    }
+
+    /// Returns the raw `IN_*` bits.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all events in `other` are in this mask.
+    pub fn contains(self, other: WatchMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for WatchMask {
+    type Output = WatchMask;
+
+    fn bitor(self, other: WatchMask) -> WatchMask {
+        WatchMask(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for WatchMask {
+    fn bitor_assign(&mut self, other: WatchMask) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Debug for WatchMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WatchMask({:#x})", self.0)
+    }
+}
+
+/// Identifies a watch added with [`add_watch()`][`Inotify::add_watch()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WatchDescriptor(i32);
+
+/// A filesystem event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InotifyEvent {
+    /// The watch that reported this event, or `None` if the event queue overflowed.
+    pub wd: Option<WatchDescriptor>,
+
+    /// What happened.
+    pub mask: WatchMask,
+
+    /// Connects the [`MOVED_FROM`][`WatchMask::MOVED_FROM`] and
+    /// [`MOVED_TO`][`WatchMask::MOVED_TO`] events of a single rename, or 0.
+    pub cookie: u32,
+
+    /// The name of the file inside a watched directory, or `None` for the watched object itself.
+    pub name: Option<OsString>,
+}
+
+/// An `inotify` instance.
+///
+/// Watches are added with [`add_watch()`][`Inotify::add_watch()`], and events are read through
+/// [`Async<Inotify>`][`crate::Async`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io::inotify::{Inotify, WatchMask};
+///
+/// let inotify = Inotify::init()?;
+/// let wd = inotify.add_watch("/etc", WatchMask::MODIFY)?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct Inotify(File);
+
+impl Inotify {
+    /// Creates an `inotify` instance.
+    pub fn init() -> io::Result<Inotify> {
+        let fd = syscall!(inotify_init1(libc::IN_CLOEXEC))?;
+        Ok(Inotify(unsafe { File::from_raw_fd(fd) }))
+    }
+
+    /// Starts watching a file or directory for the events in `mask`.
+    ///
+    /// Watching a path that is already watched replaces its mask and returns the same watch
+    /// descriptor.
+    pub fn add_watch<P: AsRef<Path>>(
+        &self,
+        path: P,
+        mask: WatchMask,
+    ) -> io::Result<WatchDescriptor> {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let wd = syscall!(inotify_add_watch(self.0.as_raw_fd(), path.as_ptr(), mask.0))?;
+        Ok(WatchDescriptor(wd))
+    }
+
+    /// Stops watching.
+    ///
+    /// An event with [`IGNORED`][`WatchMask::IGNORED`] is reported for the removed watch.
+    pub fn remove_watch(&self, wd: WatchDescriptor) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        syscall!(inotify_rm_watch(self.0.as_raw_fd(), wd.0))?;
+        #[cfg(target_os = "android")]
+        syscall!(inotify_rm_watch(self.0.as_raw_fd(), wd.0 as u32))?;
+        Ok(())
+    }
+
+    /// Reads events into a buffer and returns the number of bytes read.
+    pub(crate) fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = syscall!(read(
+            self.0.as_raw_fd(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len()
+        ))?;
+        Ok(n as usize)
+    }
+}
+
+impl AsRawFd for Inotify {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for Inotify {
+    unsafe fn from_raw_fd(fd: RawFd) -> Inotify {
+        Inotify(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for Inotify {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+/// A buffer size that always fits at least one event with the longest possible name.
+pub(crate) const BUF_SIZE: usize = 4096;
+
+/// Parses the events in a buffer filled by `read()`.
+///
+/// The kernel only returns whole events, but the parser stops at a truncated event rather than
+/// reading past the end of the buffer.
+pub(crate) fn parse(buf: &[u8], events: &mut VecDeque<InotifyEvent>) {
+    let header = mem::size_of::<libc::inotify_event>();
+    let mut offset = 0;
+
+    while buf.len() - offset >= header {
+        let raw =
+            unsafe { ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::inotify_event) };
+        let end = offset + header + raw.len as usize;
+        if end > buf.len() {
+            break;
+        }
+
+        // The name is padded with null bytes.
+        let name = &buf[offset + header..end];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+
+        events.push_back(InotifyEvent {
+            wd: if raw.wd == -1 {
+                None
+            } else {
+                Some(WatchDescriptor(raw.wd))
+            },
+            mask: WatchMask(raw.mask),
+            cookie: raw.cookie,
+            name: if name.is_empty() {
+                None
+            } else {
+                Some(OsStr::from_bytes(name).to_os_string())
+            },
+        });
+        offset = end;
+    }
+}
@@ -12,7 +12,7 @@
 use std::fmt::Debug;
 use std::future::Future;
 use std::io::{self, IoSlice, IoSliceMut, Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
 #[cfg(windows)]
 use std::os::windows::io::{AsRawSocket, RawSocket};
 use std::pin::Pin;
@@ -21,6 +21,7 @@ use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 #[cfg(unix)]
 use std::{
+    fs::File,
     os::unix::io::{AsRawFd, RawFd},
     os::unix::net::{SocketAddr as UnixSocketAddr, UnixDatagram, UnixListener, UnixStream},
     path::Path,
@@ -29,10 +30,83 @@ use std::{
 use futures_lite::*;
 use socket2::{Domain, Protocol, Socket, Type};
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::eventfd::EventFd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::inotify::{Inotify, InotifyEvent, WatchDescriptor, WatchMask};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::netlink::{NetlinkMessage, NetlinkSocket};
 use crate::parking::{Reactor, Source};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::timerfd::TimerFd;
 
+#[cfg(unix)]
+macro_rules! syscall {
+    ($fn:ident $args:tt) => {{
+        let res = unsafe { libc::$fn $args };
+        if res == -1 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }};
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use crate::ancillary::{ControlMessage, Messages, PacketInfo, RecvAncillary, SendAncillary};
+#[cfg(unix)]
+pub use crate::copy::copy_bidirectional;
+#[cfg(unix)]
+pub use crate::cred::UCred;
+#[cfg(unix)]
+pub use crate::fallback::Fallback;
+#[cfg(unix)]
+pub use crate::pipe::{pipe, PipeReader, PipeWriter};
+pub use crate::ready::{Interest, Readiness};
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+pub use crate::seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+#[cfg(unix)]
+pub use crate::stdio::{stderr, stdin, stdout, Stderr, Stdin, Stdout};
+pub use crate::tcp::{TcpKeepalive, TcpOptions};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod ancillary;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod batch;
+#[cfg(unix)]
+mod cmsg;
+#[cfg(unix)]
+mod copy;
+#[cfg(unix)]
+mod cred;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod eventfd;
+#[cfg(unix)]
+mod fallback;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod inotify;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod netlink;
 pub mod parking;
+#[cfg(unix)]
+mod pipe;
+#[cfg(unix)]
+pub mod process;
+#[cfg(unix)]
+pub mod pty;
+mod ready;
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+mod seqpacket;
+#[cfg(unix)]
+pub mod signal;
+#[cfg(unix)]
+mod stdio;
 mod sys;
+mod tcp;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod timerfd;
+#[cfg(target_os = "linux")]
+mod zerocopy;
 
 /// Fires at the chosen point in time.
 ///
@@ -141,6 +215,7 @@ impl Future for Timer {
 /// [`Stdout`][`std::io::Stdout`], or [`Stderr`][`std::io::Stderr`] because they're not
 /// supported. Wrap them in
 /// [`blocking::Unblock`](https://docs.rs/blocking/*/blocking/struct.Unblock.html) instead.
+/// On Unix, use [`stdin()`], [`stdout()`], and [`stderr()`] for the standard streams.
 ///
 /// # Examples
 ///
@@ -221,8 +296,10 @@ impl<T: AsRawFd> Async<T> {
     ///
     /// **NOTE**: Do not use this type with [`File`][`std::fs::File`], [`Stdin`][`std::io::Stdin`],
     /// [`Stdout`][`std::io::Stdout`], or [`Stderr`][`std::io::Stderr`] because they're not
-    /// supported. Wrap them in
-    /// [`blocking::Unblock`](https://docs.rs/blocking/*/blocking/struct.Unblock.html) instead.
+    /// supported. On Linux and Android, epoll rejects regular files and directories with an error
+    /// of kind [`io::ErrorKind::InvalidInput`], and the handle is left in blocking mode. Use
+    /// [`Async::new_blocking_fallback()`] to serve such handles from a thread pool instead, and
+    /// [`stdin()`], [`stdout()`], and [`stderr()`] for the standard streams.
     ///
     /// [epoll]: https://en.wikipedia.org/wiki/Epoll
     /// [kqueue]: https://en.wikipedia.org/wiki/Kqueue
@@ -247,6 +324,41 @@ impl<T: AsRawFd> Async<T> {
     }
 }
 
+#[cfg(unix)]
+impl<T: AsRawFd + Send + Sync + 'static> Async<T> {
+    /// Creates an async I/O handle, falling back to a thread pool if the reactor doesn't support
+    /// it.
+    ///
+    /// Handles that [`Async::new()`] accepts are registered in the reactor. Others, such as
+    /// regular files, are left in blocking mode and read and written on a small thread pool. The
+    /// returned [`Fallback`] implements [`AsyncRead`] and [`AsyncWrite`] either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use futures_lite::*;
+    /// use std::fs::File;
+    ///
+    /// # blocking::block_on(async {
+    /// let mut file = Async::new_blocking_fallback(File::open("Cargo.toml")?)?;
+    ///
+    /// let mut contents = String::new();
+    /// file.read_to_string(&mut contents).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn new_blocking_fallback(io: T) -> io::Result<Fallback<T>> {
+        match Reactor::get().insert_io(io.as_raw_fd()) {
+            Ok(source) => Ok(Fallback::reactor(Async {
+                source,
+                io: Some(Box::new(io)),
+            })),
+            Err(err) if sys::is_unsupported(&err) => Ok(Fallback::pool(io)),
+            Err(err) => Err(err),
+        }
+    }
+}
+
 #[cfg(unix)]
 impl<T: AsRawFd> AsRawFd for Async<T> {
     fn as_raw_fd(&self) -> RawFd {
@@ -375,7 +487,7 @@ impl<T> Async<T> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn readable(&self) -> io::Result<()> {
-        self.source.readable().await
+        self.source.readable().await.map(drop)
     }
 
     /// Waits until the I/O handle is writable.
@@ -397,7 +509,41 @@ impl<T> Async<T> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn writable(&self) -> io::Result<()> {
-        self.source.writable().await
+        self.source.writable().await.map(drop)
+    }
+
+    /// Waits until the I/O handle is ready for any of the events in `interest`.
+    ///
+    /// Unlike [`readable()`][`Async::readable()`] and [`writable()`][`Async::writable()`], this
+    /// function reports what the OS signaled: besides readability and writability, the returned
+    /// [`Readiness`] tells whether the peer closed its half of the connection, whether an error
+    /// is pending, and whether priority data such as TCP urgent data has arrived.
+    ///
+    /// Events are collected until a wait for their direction returns, so readiness for one
+    /// direction isn't lost when an event for the other direction arrives in between. Waits in
+    /// [`read_with()`][`Async::read_with()`], [`write_with()`][`Async::write_with()`], and the
+    /// I/O traits consume readiness too, so it is never reported again after being handled. If
+    /// `interest` covers both directions, only the readiness of the directions whose events
+    /// arrived is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, Interest};
+    /// use std::net::TcpStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// // Check whether the peer has stopped sending.
+    /// let readiness = stream.ready(Interest::READABLE).await?;
+    /// if readiness.is_read_closed() {
+    ///     println!("peer closed its write half");
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn ready(&self, interest: Interest) -> io::Result<Readiness> {
+        self.source.ready(interest).await
     }
 
     /// Performs a read operation asynchronously.
@@ -673,7 +819,37 @@ impl Async<TcpListener> {
     /// ```
     pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<TcpListener>> {
         let addr = addr.into();
-        Ok(Async::new(TcpListener::bind(addr)?)?)
+        Async::new(TcpListener::bind(addr)?)
+    }
+
+    /// Enables TCP Fast Open on this listener (`TCP_FASTOPEN`).
+    ///
+    /// Clients that have a Fast Open cookie can then send data in the SYN packet, which the
+    /// listener accepts before the handshake completes. The `queue_len` limits the number of
+    /// pending Fast Open requests that haven't been accepted yet.
+    ///
+    /// Whether the server side is actually used also depends on the `net.ipv4.tcp_fastopen`
+    /// sysctl.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::TcpListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+    /// listener.set_fastopen(16)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_fastopen(&self, queue_len: u32) -> io::Result<()> {
+        sys::setsockopt(
+            self.source.raw,
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN,
+            queue_len as libc::c_int,
+        )
     }
 
     /// Accepts a new incoming TCP connection.
@@ -681,6 +857,11 @@ impl Async<TcpListener> {
     /// When a connection is established, it will be returned as a TCP stream together with its
     /// remote address.
     ///
+    /// Not all platforms let accepted streams inherit socket options from the listener, so options
+    /// like keepalive and `TCP_NODELAY` should be configured on the returned stream, e.g. with
+    /// [`Async::<TcpStream>::set_keepalive()`] and [`Async::<TcpStream>::set_nodelay()`], or with
+    /// [`Async::<TcpListener>::accept_with()`].
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -690,6 +871,7 @@ impl Async<TcpListener> {
     /// # blocking::block_on(async {
     /// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
     /// let (stream, addr) = listener.accept().await?;
+    /// stream.set_nodelay(true)?;
     /// println!("Accepted client: {}", addr);
     /// # std::io::Result::Ok(()) });
     /// ```
@@ -698,6 +880,36 @@ impl Async<TcpListener> {
         Ok((Async::new(stream)?, addr))
     }
 
+    /// Accepts a new incoming TCP connection and sets the given socket options on it.
+    ///
+    /// If setting an option fails, the connection is closed and the error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, TcpKeepalive, TcpOptions};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
+    /// let options = TcpOptions::new()
+    ///     .with_keepalive(TcpKeepalive::new().with_time(Duration::from_secs(60)))
+    ///     .with_nodelay(true);
+    ///
+    /// let (stream, addr) = listener.accept_with(&options).await?;
+    /// println!("Accepted client: {}", addr);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn accept_with(
+        &self,
+        options: &TcpOptions,
+    ) -> io::Result<(Async<TcpStream>, SocketAddr)> {
+        let (stream, addr) = self.accept().await?;
+        options.apply(&stream)?;
+        Ok((stream, addr))
+    }
+
     /// Returns a stream of incoming TCP connections.
     ///
     /// The stream is infinite, i.e. it never stops with a [`None`].
@@ -725,6 +937,40 @@ impl Async<TcpListener> {
             Some((res, listener))
         }))
     }
+
+    /// Returns a stream of incoming TCP connections with the given socket options set on them.
+    ///
+    /// The stream is infinite, i.e. it never stops with a [`None`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, TcpOptions};
+    /// use futures::prelude::*;
+    /// use std::net::TcpListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
+    /// let mut incoming = listener.incoming_with(TcpOptions::new().with_nodelay(true));
+    ///
+    /// while let Some(stream) = incoming.next().await {
+    ///     let stream = stream?;
+    ///     println!("Accepted client: {}", stream.get_ref().peer_addr()?);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn incoming_with(
+        &self,
+        options: TcpOptions,
+    ) -> impl Stream<Item = io::Result<Async<TcpStream>>> + Send + Unpin + '_ {
+        Box::pin(stream::unfold(self, move |listener| async move {
+            let res = listener
+                .accept_with(&options)
+                .await
+                .map(|(stream, _)| stream);
+            Some((res, listener))
+        }))
+    }
 }
 
 impl Async<TcpStream> {
@@ -780,6 +1026,108 @@ impl Async<TcpStream> {
         }
     }
 
+    /// Creates a TCP connection to the specified address and sends `data` as the first bytes.
+    ///
+    /// On Linux and Android, this uses TCP Fast Open (`TCP_FASTOPEN_CONNECT`), which carries the
+    /// data in the SYN packet when the client has a Fast Open cookie from an earlier connection to
+    /// the same server. That saves a round trip before the server sees the first request.
+    ///
+    /// When Fast Open is not supported or not enabled, this method falls back to a regular
+    /// connect followed by a write. Either way, all of `data` has been written when it returns.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::{TcpStream, ToSocketAddrs};
+    ///
+    /// # blocking::block_on(async {
+    /// let addr = "example.com:80".to_socket_addrs()?.next().unwrap();
+    /// let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+    /// let stream = Async::<TcpStream>::connect_with_data(addr, request).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn connect_with_data<A: Into<SocketAddr>>(
+        addr: A,
+        data: &[u8],
+    ) -> io::Result<Async<TcpStream>> {
+        let addr = addr.into();
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let mut stream = match Async::<TcpStream>::connect_fastopen(addr, data).await? {
+            Some((mut stream, written)) => {
+                stream.write_all(&data[written..]).await?;
+                return Ok(stream);
+            }
+            None => Async::<TcpStream>::connect(addr).await?,
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let mut stream = Async::<TcpStream>::connect(addr).await?;
+
+        stream.write_all(data).await?;
+        Ok(stream)
+    }
+
+    /// Begins a TCP Fast Open connection and writes as much of `data` as the kernel accepts.
+    ///
+    /// Returns `None` if Fast Open is not available on this system.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    async fn connect_fastopen(
+        addr: SocketAddr,
+        data: &[u8],
+    ) -> io::Result<Option<(Async<TcpStream>, usize)>> {
+        // A deferred connect only begins with the first non-empty write.
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        // Create a socket with connects deferred until the first write.
+        let domain = if addr.is_ipv6() {
+            Domain::ipv6()
+        } else {
+            Domain::ipv4()
+        };
+        let socket = Socket::new(domain, Type::stream(), Some(Protocol::tcp()))?;
+        let res = sys::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN_CONNECT,
+            1 as libc::c_int,
+        );
+        match res {
+            Err(err) if err.raw_os_error() == Some(libc::ENOPROTOOPT) => return Ok(None),
+            res => res?,
+        }
+
+        // Without a cookie, connect begins a regular handshake and reports "in progress".
+        socket.set_nonblocking(true)?;
+        socket.connect(&addr.into()).or_else(|err| {
+            if err.raw_os_error() == Some(libc::EINPROGRESS) {
+                Ok(())
+            } else {
+                Err(err)
+            }
+        })?;
+        let stream = Async::new(socket.into_tcp_stream())?;
+
+        // The first write sends the SYN, with data if there's a cookie. If the handshake is still
+        // in progress, wait until the stream becomes writable and try again.
+        let written = stream
+            .write_with(|io| match (&*io).write(data) {
+                Err(err) if err.raw_os_error() == Some(libc::EINPROGRESS) => {
+                    Err(io::ErrorKind::WouldBlock.into())
+                }
+                res => res,
+            })
+            .await?;
+
+        // Check if there was an error while connecting.
+        match stream.get_ref().take_error()? {
+            None => Ok(Some((stream, written))),
+            Some(err) => Err(err),
+        }
+    }
+
     /// Reads data from the stream without removing it from the buffer.
     ///
     /// Returns the number of bytes read. Successive calls of this method read the same data.
@@ -806,455 +1154,2831 @@ impl Async<TcpStream> {
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.read_with(|io| io.peek(buf)).await
     }
-}
 
-impl Async<UdpSocket> {
-    /// Creates a UDP socket bound to the specified address.
+    /// Shuts down the read, write, or both halves of this connection.
     ///
-    /// Binding with port number 0 will request an available port from the OS.
+    /// Tasks blocked reading from or writing to the shut down halves are woken up, so that they
+    /// observe end of stream or a "broken pipe" error right away.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::{Shutdown, TcpStream};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
-    /// println!("Bound to {}", socket.get_ref().local_addr()?);
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// stream.shutdown(Shutdown::Both)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<UdpSocket>> {
-        let addr = addr.into();
-        Ok(Async::new(UdpSocket::bind(addr)?)?)
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.get_ref().shutdown(how)?;
+        self.source.wake(
+            how == Shutdown::Read || how == Shutdown::Both,
+            how == Shutdown::Write || how == Shutdown::Both,
+        );
+        Ok(())
     }
 
-    /// Receives a single datagram message.
+    /// Sends `len` bytes of a file starting at `offset` into the stream.
     ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// Returns the number of bytes sent, which is less than `len` only if the end of the file was
+    /// reached. The file's own position is not changed.
     ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// On Linux, the data is transferred with `sendfile()` without copying it into userspace.
+    /// Elsewhere, or if the file doesn't support `sendfile()`, the data is read into a buffer and
+    /// then written into the stream. Between partial transfers, this method waits for the stream to
+    /// become writable.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::fs::File;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// let file = File::open("index.html")?;
+    /// let len = file.metadata()?.len() as usize;
+    /// stream.sendfile(&file, 0, len).await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.read_with(|io| io.recv_from(buf)).await
+    #[cfg(unix)]
+    pub async fn sendfile(&self, file: &File, offset: u64, len: usize) -> io::Result<usize> {
+        let mut sent = 0;
+        let mut buf = Vec::new();
+        #[cfg(target_os = "linux")]
+        let mut zero_copy = true;
+
+        while sent < len {
+            let offset = offset + sent as u64;
+            let count = len - sent;
+            let n = self
+                .write_with(|io| {
+                    #[cfg(target_os = "linux")]
+                    {
+                        if zero_copy {
+                            match copy::sendfile(io.as_raw_fd(), file.as_raw_fd(), offset, count) {
+                                Err(err) if copy::is_unsupported(&err) => zero_copy = false,
+                                res => return res,
+                            }
+                        }
+                    }
+                    buf.resize(copy::CHUNK_SIZE, 0);
+                    copy::pread_write(io.as_raw_fd(), file.as_raw_fd(), offset, count, &mut buf)
+                })
+                .await?;
+            if n == 0 {
+                break;
+            }
+            sent += n;
+        }
+        Ok(sent)
     }
 
-    /// Receives a single datagram message without removing it from the queue.
+    /// Sends all of `buf` without copying it into the kernel (`MSG_ZEROCOPY`).
     ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// The kernel transmits data directly from the pages of `buf`, so the buffer is held until
+    /// the reactor observes completion notifications for every send on the socket error queue.
+    /// Then the buffer is returned to the caller for reuse.
     ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// Zero-copy sends pay off only for large buffers, typically from 10 KB upwards. The kernel
+    /// may still decide to copy the data, e.g. on the loopback interface. If the system doesn't
+    /// support zero-copy sends, the data is sent normally.
+    ///
+    /// Each send is numbered and the buffer is returned only once completion notifications cover
+    /// all of its sends. If the returned future is dropped before that, the socket takes over the
+    /// buffer and drops it once the reactor observes the remaining notifications while waiting for
+    /// any event on the socket, or when the socket is closed.
+    ///
+    /// While zero-copy sends are outstanding, the socket error queue (`MSG_ERRQUEUE`) belongs to
+    /// this method. Other entries read from it in the meantime, such as errors enabled with
+    /// `IP_RECVERR` or timestamps enabled with `SO_TIMESTAMPING`, are discarded.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// let mut stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let (len, addr) = socket.peek_from(&mut buf).await?;
+    /// let buf = vec![0u8; 1 << 20];
+    /// let buf = stream.send_zerocopy(buf).await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.read_with(|io| io.peek_from(buf)).await
+    #[cfg(target_os = "linux")]
+    pub async fn send_zerocopy<B>(&mut self, buf: B) -> io::Result<B>
+    where
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        let mut zero_copy = match zerocopy::enable(self.source.raw) {
+            Err(err) if zerocopy::is_unsupported(&err) => false,
+            res => res.map(|()| true)?,
+        };
+
+        // The buffer is boxed so that its data stays in place if the socket takes it over.
+        let mut pending = zerocopy::Pending {
+            source: &self.source,
+            buf: Some(Box::new(buf)),
+            seqs: None,
+        };
+        let data = (**pending.buf.as_ref().unwrap()).as_ref();
+        let mut sent = 0;
+        let mut res = Ok(());
+
+        while sent < data.len() {
+            let send = self
+                .write_with(|io| {
+                    let n = zerocopy::send(io.as_raw_fd(), &data[sent..], zero_copy)?;
+                    let seq = if zero_copy {
+                        Some(self.source.zerocopy.lock().unwrap().submit())
+                    } else {
+                        None
+                    };
+                    Ok((n, seq))
+                })
+                .await;
+            match send {
+                Ok((n, seq)) => {
+                    sent += n;
+                    if let Some(seq) = seq {
+                        let first = pending.seqs.map_or(seq, |(first, _)| first);
+                        pending.seqs = Some((first, seq));
+                    }
+                }
+                // Too many pages are pinned by pending sends, so wait until some complete or
+                // send the rest normally if there is nothing to wait for.
+                Err(err) if err.raw_os_error() == Some(libc::ENOBUFS) => match pending.seqs {
+                    Some((first, last)) if !self.zerocopy_done(first, last) => {
+                        self.source.zerocopy_completed(first, last).await?
+                    }
+                    _ => zero_copy = false,
+                },
+                Err(err) => {
+                    res = Err(err);
+                    break;
+                }
+            }
+        }
+
+        // Hold on to the buffer until the kernel is done with it, even if sending failed.
+        if let Some((first, last)) = pending.seqs {
+            self.source.zerocopy_completed(first, last).await?;
+        }
+        let buf = pending.buf.take().unwrap();
+        res.map(|()| *buf)
     }
 
-    /// Sends data to the specified address.
-    ///
-    /// Returns the number of bytes writen.
+    /// Returns `true` if the zero-copy sends numbered `first..=last` have completed.
+    #[cfg(target_os = "linux")]
+    fn zerocopy_done(&self, first: u32, last: u32) -> bool {
+        self.source.zerocopy.lock().unwrap().covers(first, last)
+    }
+
+    /// Enables keepalive probes with the given parameters, or disables them with `None`.
     ///
     /// # Examples
     ///
     /// ```no_run
-    /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use async_io::{Async, TcpKeepalive};
+    /// use std::net::TcpStream;
+    /// use std::time::Duration;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
-    /// let addr = socket.get_ref().local_addr()?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
     ///
-    /// let msg = b"hello";
-    /// let len = socket.send_to(msg, addr).await?;
+    /// let keepalive = TcpKeepalive::new()
+    ///     .with_time(Duration::from_secs(60))
+    ///     .with_interval(Duration::from_secs(10));
+    /// stream.set_keepalive(Some(keepalive))?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send_to<A: Into<SocketAddr>>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
-        let addr = addr.into();
-        self.write_with(|io| io.send_to(buf, addr)).await
+    pub fn set_keepalive(&self, keepalive: Option<TcpKeepalive>) -> io::Result<()> {
+        tcp::set_keepalive(&sys::socket_ref(self.source.raw), keepalive.as_ref())
     }
 
-    /// Receives a single datagram message from the connected peer.
-    ///
-    /// Returns the number of bytes read.
-    ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
-    ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// Returns the keepalive parameters, or `None` if keepalive probes are disabled.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
-    ///
-    /// let mut buf = [0u8; 1024];
-    /// let len = socket.recv(&mut buf).await?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// println!("Keepalive: {:?}", stream.keepalive()?);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.read_with(|io| io.recv(buf)).await
+    pub fn keepalive(&self) -> io::Result<Option<TcpKeepalive>> {
+        tcp::keepalive(&sys::socket_ref(self.source.raw))
     }
 
-    /// Receives a single datagram message from the connected peer without removing it from the
-    /// queue.
+    /// Sets how long closing the stream waits for unsent data to be transmitted (`SO_LINGER`).
     ///
-    /// Returns the number of bytes read and the address the message came from.
-    ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
-    ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// With `None`, the stream is closed in the background. With a zero duration, unsent data is
+    /// discarded and the connection is reset when the stream is dropped.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
+    /// use std::time::Duration;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
-    ///
-    /// let mut buf = [0u8; 1024];
-    /// let len = socket.peek(&mut buf).await?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// stream.set_linger(Some(Duration::from_secs(5)))?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.read_with(|io| io.peek(buf)).await
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        sys::socket_ref(self.source.raw).set_linger(linger)
     }
 
-    /// Sends data to the connected peer.
-    ///
-    /// Returns the number of bytes written.
-    ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// Returns the value of the `SO_LINGER` option.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
-    ///
-    /// let msg = b"hello";
-    /// let len = socket.send(msg).await?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// println!("Linger: {:?}", stream.linger()?);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        self.write_with(|io| io.send(buf)).await
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        sys::socket_ref(self.source.raw).linger()
     }
-}
 
-#[cfg(unix)]
-impl Async<UnixListener> {
-    /// Creates a UDS listener bound to the specified path.
+    /// Enables or disables Nagle's algorithm (`TCP_NODELAY`).
+    ///
+    /// With `TCP_NODELAY` set, small writes are sent immediately instead of being coalesced.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixListener;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
-    /// println!("Listening on {:?}", listener.get_ref().local_addr()?);
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// stream.set_nodelay(true)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixListener>> {
-        let path = path.as_ref().to_owned();
-        Ok(Async::new(UnixListener::bind(path)?)?)
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.get_ref().set_nodelay(nodelay)
     }
 
-    /// Accepts a new incoming UDS stream connection.
-    ///
-    /// When a connection is established, it will be returned as a stream together with its remote
-    /// address.
+    /// Returns the value of the `TCP_NODELAY` option.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixListener;
+    /// use std::net::TcpStream;
     ///
     /// # blocking::block_on(async {
-    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
-    /// let (stream, addr) = listener.accept().await?;
-    /// println!("Accepted client: {:?}", addr);
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// println!("Nodelay: {}", stream.nodelay()?);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn accept(&self) -> io::Result<(Async<UnixStream>, UnixSocketAddr)> {
-        let (stream, addr) = self.read_with(|io| io.accept()).await?;
-        Ok((Async::new(stream)?, addr))
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.get_ref().nodelay()
     }
 
-    /// Returns a stream of incoming UDS connections.
+    /// Sets how long transmitted data may stay unacknowledged before the connection is dropped
+    /// (`TCP_USER_TIMEOUT`).
     ///
-    /// The stream is infinite, i.e. it never stops with a [`None`] item.
+    /// This bounds how long a write to a dead peer goes unnoticed, even while keepalive probes
+    /// aren't sent because there's unacknowledged data. With `None`, the system default is used.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use futures::prelude::*;
-    /// use std::os::unix::net::UnixListener;
+    /// use std::net::TcpStream;
+    /// use std::time::Duration;
     ///
     /// # blocking::block_on(async {
-    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
-    /// let mut incoming = listener.incoming();
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// stream.set_user_timeout(Some(Duration::from_secs(30)))?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_user_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        #[allow(clippy::legacy_numeric_constants)]
+        let millis = timeout.map_or(0, |t| {
+            t.as_millis().max(1).min(libc::c_int::max_value() as u128)
+        });
+        sys::setsockopt(
+            self.source.raw,
+            libc::IPPROTO_TCP,
+            libc::TCP_USER_TIMEOUT,
+            millis as libc::c_int,
+        )
+    }
+
+    /// Returns the value of the `TCP_USER_TIMEOUT` option, or `None` if the system default is
+    /// used.
     ///
-    /// while let Some(stream) = incoming.next().await {
-    ///     let stream = stream?;
-    ///     println!("Accepted client: {:?}", stream.get_ref().peer_addr()?);
-    /// }
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8080)).await?;
+    /// println!("User timeout: {:?}", stream.user_timeout()?);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn incoming(
-        &self,
-    ) -> impl Stream<Item = io::Result<Async<UnixStream>>> + Send + Unpin + '_ {
-        Box::pin(stream::unfold(self, |listener| async move {
-            let res = listener.accept().await.map(|(stream, _)| stream);
-            Some((res, listener))
-        }))
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn user_timeout(&self) -> io::Result<Option<Duration>> {
+        let millis: libc::c_int =
+            sys::getsockopt(self.source.raw, libc::IPPROTO_TCP, libc::TCP_USER_TIMEOUT)?;
+        Ok(match millis {
+            0 => None,
+            millis => Some(Duration::from_millis(millis as u64)),
+        })
     }
 }
 
-#[cfg(unix)]
-impl Async<UnixStream> {
-    /// Creates a UDS stream connected to the specified path.
+impl Async<UdpSocket> {
+    /// Creates a UDP socket bound to the specified address.
+    ///
+    /// Binding with port number 0 will request an available port from the OS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// println!("Bound to {}", socket.get_ref().local_addr()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<UdpSocket>> {
+        let addr = addr.into();
+        Async::new(UdpSocket::bind(addr)?)
+    }
+
+    /// Creates a UDP socket bound to `local` and connected to `remote`.
+    ///
+    /// A connected socket can use [`recv()`][`Async::<UdpSocket>::recv()`] and
+    /// [`send()`][`Async::<UdpSocket>::send()`], and only receives datagrams from `remote`.
+    /// Errors reported asynchronously by the peer's host, such as "connection refused" caused by
+    /// an ICMP message, are returned by subsequent receives and sends.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixStream;
+    /// use std::net::UdpSocket;
     ///
     /// # blocking::block_on(async {
-    /// let stream = Async::<UnixStream>::connect("/tmp/socket").await?;
+    /// let socket = Async::<UdpSocket>::connect(([0, 0, 0, 0], 0), ([127, 0, 0, 1], 9000)).await?;
+    /// socket.send(b"hello").await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixStream>> {
-        // Create a socket.
-        let socket = Socket::new(Domain::unix(), Type::stream(), None)?;
+    pub async fn connect<A: Into<SocketAddr>, B: Into<SocketAddr>>(
+        local: A,
+        remote: B,
+    ) -> io::Result<Async<UdpSocket>> {
+        let socket = Async::<UdpSocket>::bind(local)?;
+        socket.get_ref().connect(remote.into())?;
+        Ok(socket)
+    }
 
-        // Begin async connect and ignore the inevitable "in progress" error.
-        socket.set_nonblocking(true)?;
-        socket
-            .connect(&socket2::SockAddr::unix(path)?)
-            .or_else(|err| {
-                if err.raw_os_error() == Some(libc::EINPROGRESS) {
-                    Ok(())
-                } else {
-                    Err(err)
-                }
-            })?;
-        let stream = Async::new(socket.into_unix_stream())?;
+    /// Creates a UDP socket for receiving multicast datagrams on the specified address.
+    ///
+    /// The socket has `SO_REUSEADDR` set so that multiple sockets on the same host can receive
+    /// datagrams sent to the same group and port. On Windows, which can't bind to multicast
+    /// addresses, a multicast address is replaced with the wildcard address of the same family.
+    ///
+    /// If an IPv6 address has a non-zero scope ID, it also selects the interface for outgoing
+    /// multicast datagrams.
+    ///
+    /// The socket still needs to join groups with methods like
+    /// [`join_multicast_v4()`][`Async::<UdpSocket>::join_multicast_v4()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::{Ipv4Addr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 0))?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind_multicast<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<UdpSocket>> {
+        let addr = addr.into();
 
-        // The stream becomes writable when connected.
-        stream.writable().await?;
+        // Create a socket that shares the port with other multicast receivers.
+        let domain = if addr.is_ipv6() {
+            Domain::ipv6()
+        } else {
+            Domain::ipv4()
+        };
+        let socket = Socket::new(domain, Type::dgram(), Some(Protocol::udp()))?;
+        socket.set_reuse_address(true)?;
+
+        #[cfg(windows)]
+        let addr = if addr.ip().is_multicast() {
+            match addr {
+                SocketAddr::V4(a) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, a.port())),
+                SocketAddr::V6(a) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, a.port())),
+            }
+        } else {
+            addr
+        };
+        socket.bind(&addr.into())?;
 
-        Ok(stream)
+        if let SocketAddr::V6(a) = addr {
+            if a.scope_id() != 0 {
+                socket.set_multicast_if_v6(a.scope_id())?;
+            }
+        }
+        Async::new(socket.into_udp_socket())
     }
 
-    /// Creates an unnamed pair of connected UDS stream sockets.
+    /// Joins an IPv4 multicast group on the interface with the specified address.
+    ///
+    /// If the interface is [`Ipv4Addr::UNSPECIFIED`], the OS picks an interface.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixStream;
+    /// use std::net::{Ipv4Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 5353))?;
+    /// socket.join_multicast_v4(Ipv4Addr::new(224, 0, 0, 251), Ipv4Addr::UNSPECIFIED)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn pair() -> io::Result<(Async<UnixStream>, Async<UnixStream>)> {
-        let (stream1, stream2) = UnixStream::pair()?;
-        Ok((Async::new(stream1)?, Async::new(stream2)?))
+    pub fn join_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.get_ref().join_multicast_v4(&group, &interface)
     }
-}
 
-#[cfg(unix)]
-impl Async<UnixDatagram> {
-    /// Creates a UDS datagram socket bound to the specified path.
+    /// Leaves an IPv4 multicast group previously joined on the specified interface.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv4Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket")?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 5353))?;
+    /// let group = Ipv4Addr::new(224, 0, 0, 251);
+    /// socket.join_multicast_v4(group, Ipv4Addr::UNSPECIFIED)?;
+    /// socket.leave_multicast_v4(group, Ipv4Addr::UNSPECIFIED)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixDatagram>> {
-        let path = path.as_ref().to_owned();
-        Ok(Async::new(UnixDatagram::bind(path)?)?)
+    pub fn leave_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.get_ref().leave_multicast_v4(&group, &interface)
     }
 
-    /// Creates a UDS datagram socket not bound to any address.
+    /// Joins an IPv6 multicast group on the interface with the specified index.
+    ///
+    /// If the interface index is 0, the OS picks an interface. For link-local groups, pass the
+    /// scope ID of the link as the interface index.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv6Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::unbound()?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv6Addr::UNSPECIFIED, 5353))?;
+    /// socket.join_multicast_v6("ff02::fb".parse().unwrap(), 0)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn unbound() -> io::Result<Async<UnixDatagram>> {
-        Ok(Async::new(UnixDatagram::unbound()?)?)
+    pub fn join_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.get_ref().join_multicast_v6(&group, interface)
     }
 
-    /// Creates an unnamed pair of connected Unix datagram sockets.
+    /// Leaves an IPv6 multicast group previously joined on the specified interface.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv6Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv6Addr::UNSPECIFIED, 5353))?;
+    /// let group = "ff02::fb".parse().unwrap();
+    /// socket.join_multicast_v6(group, 0)?;
+    /// socket.leave_multicast_v6(group, 0)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn pair() -> io::Result<(Async<UnixDatagram>, Async<UnixDatagram>)> {
-        let (socket1, socket2) = UnixDatagram::pair()?;
-        Ok((Async::new(socket1)?, Async::new(socket2)?))
+    pub fn leave_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.get_ref().leave_multicast_v6(&group, interface)
     }
 
-    /// Receives data from the socket.
+    /// Joins a source-specific IPv4 multicast group (`IP_ADD_SOURCE_MEMBERSHIP`).
     ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// Only datagrams sent to `group` by `source` are received. If the interface is
+    /// [`Ipv4Addr::UNSPECIFIED`], the OS picks an interface.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv4Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket")?;
-    ///
-    /// let mut buf = [0u8; 1024];
-    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 5000))?;
+    /// let source = Ipv4Addr::new(192, 168, 0, 1);
+    /// let group = Ipv4Addr::new(232, 1, 1, 1);
+    /// socket.join_ssm_v4(source, group, Ipv4Addr::UNSPECIFIED)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
-        self.read_with(|io| io.recv_from(buf)).await
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+    ))]
+    pub fn join_ssm_v4(
+        &self,
+        source: Ipv4Addr,
+        group: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        let mreq = ip_mreq_source(source, group, interface);
+        sys::setsockopt(
+            self.source.raw,
+            libc::IPPROTO_IP,
+            libc::IP_ADD_SOURCE_MEMBERSHIP,
+            mreq,
+        )
     }
 
-    /// Sends data to the specified address.
-    ///
-    /// Returns the number of bytes written.
+    /// Leaves a source-specific IPv4 multicast group (`IP_DROP_SOURCE_MEMBERSHIP`).
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv4Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::unbound()?;
+    /// let socket = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 5000))?;
+    /// let source = Ipv4Addr::new(192, 168, 0, 1);
+    /// let group = Ipv4Addr::new(232, 1, 1, 1);
+    /// socket.join_ssm_v4(source, group, Ipv4Addr::UNSPECIFIED)?;
+    /// socket.leave_ssm_v4(source, group, Ipv4Addr::UNSPECIFIED)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+    ))]
+    pub fn leave_ssm_v4(
+        &self,
+        source: Ipv4Addr,
+        group: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        let mreq = ip_mreq_source(source, group, interface);
+        sys::setsockopt(
+            self.source.raw,
+            libc::IPPROTO_IP,
+            libc::IP_DROP_SOURCE_MEMBERSHIP,
+            mreq,
+        )
+    }
+
+    /// Sets the interface for outgoing IPv4 multicast datagrams by its address.
     ///
-    /// let msg = b"hello";
-    /// let addr = "/tmp/socket";
-    /// let len = socket.send_to(msg, addr).await?;
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::{Ipv4Addr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_multicast_if_v4(Ipv4Addr::LOCALHOST)?;
+    /// assert_eq!(socket.multicast_if_v4()?, Ipv4Addr::LOCALHOST);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> io::Result<usize> {
-        self.write_with(|io| io.send_to(buf, &path)).await
+    pub fn set_multicast_if_v4(&self, interface: Ipv4Addr) -> io::Result<()> {
+        sys::socket_ref(self.source.raw).set_multicast_if_v4(&interface)
     }
 
-    /// Receives data from the connected peer.
+    /// Gets the interface for outgoing IPv4 multicast datagrams.
     ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// println!("Sending multicast from {}", socket.multicast_if_v4()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn multicast_if_v4(&self) -> io::Result<Ipv4Addr> {
+        sys::socket_ref(self.source.raw).multicast_if_v4()
+    }
+
+    /// Sets the interface for outgoing IPv6 multicast datagrams by its index.
     ///
-    /// The [`connect`][`UnixDatagram::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// An index of 0 lets the OS pick the interface.
     ///
     /// # Examples
     ///
-    /// ```no_run
+    /// ```
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::{Ipv6Addr, UdpSocket};
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket1")?;
-    /// socket.get_ref().connect("/tmp/socket2")?;
+    /// let socket = Async::<UdpSocket>::bind((Ipv6Addr::UNSPECIFIED, 0))?;
+    /// socket.set_multicast_if_v6(0)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_multicast_if_v6(&self, interface: u32) -> io::Result<()> {
+        sys::socket_ref(self.source.raw).set_multicast_if_v6(interface)
+    }
+
+    /// Gets the index of the interface for outgoing IPv6 multicast datagrams.
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let len = socket.recv(&mut buf).await?;
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::{Ipv6Addr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind((Ipv6Addr::UNSPECIFIED, 0))?;
+    /// println!("Sending multicast on interface {}", socket.multicast_if_v6()?);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.read_with(|io| io.recv(buf)).await
+    pub fn multicast_if_v6(&self) -> io::Result<u32> {
+        sys::socket_ref(self.source.raw).multicast_if_v6()
     }
 
-    /// Sends data to the connected peer.
+    /// Sets whether outgoing multicast datagrams are looped back to local sockets.
     ///
-    /// Returns the number of bytes written.
+    /// This sets `IP_MULTICAST_LOOP` or `IPV6_MULTICAST_LOOP` depending on the address family
+    /// of the socket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
     ///
-    /// The [`connect`][`UnixDatagram::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_multicast_loop(false)?;
+    /// assert!(!socket.multicast_loop()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_multicast_loop(&self, on: bool) -> io::Result<()> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            self.get_ref().set_multicast_loop_v6(on)
+        } else {
+            self.get_ref().set_multicast_loop_v4(on)
+        }
+    }
+
+    /// Gets whether outgoing multicast datagrams are looped back to local sockets.
     ///
     /// # Examples
     ///
-    /// ```no_run
+    /// ```
     /// use async_io::Async;
-    /// use std::os::unix::net::UnixDatagram;
+    /// use std::net::UdpSocket;
     ///
     /// # blocking::block_on(async {
-    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket1")?;
-    /// socket.get_ref().connect("/tmp/socket2")?;
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// println!("Multicast loopback: {}", socket.multicast_loop()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn multicast_loop(&self) -> io::Result<bool> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            self.get_ref().multicast_loop_v6()
+        } else {
+            self.get_ref().multicast_loop_v4()
+        }
+    }
+
+    /// Sets the time-to-live or hop limit of outgoing multicast datagrams.
     ///
-    /// let msg = b"hello";
-    /// let len = socket.send(msg).await?;
+    /// This sets `IP_MULTICAST_TTL` or `IPV6_MULTICAST_HOPS` depending on the address family of
+    /// the socket. A value of 1 keeps datagrams on the local network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_multicast_ttl(4)?;
+    /// assert_eq!(socket.multicast_ttl()?, 4);
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        self.write_with(|io| io.send(buf)).await
+    pub fn set_multicast_ttl(&self, ttl: u32) -> io::Result<()> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            sys::socket_ref(self.source.raw).set_multicast_hops_v6(ttl)
+        } else {
+            self.get_ref().set_multicast_ttl_v4(ttl)
+        }
+    }
+
+    /// Gets the time-to-live or hop limit of outgoing multicast datagrams.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// println!("Multicast TTL: {}", socket.multicast_ttl()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn multicast_ttl(&self) -> io::Result<u32> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            sys::socket_ref(self.source.raw).multicast_hops_v6()
+        } else {
+            self.get_ref().multicast_ttl_v4()
+        }
+    }
+
+    /// Receives a single datagram message.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.read_with(|io| io.recv_from(buf)).await
+    }
+
+    /// Receives a single datagram message without removing it from the queue.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, addr) = socket.peek_from(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.read_with(|io| io.peek_from(buf)).await
+    }
+
+    /// Sends data to the specified address.
+    ///
+    /// Returns the number of bytes writen.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// let addr = socket.get_ref().local_addr()?;
+    ///
+    /// let msg = b"hello";
+    /// let len = socket.send_to(msg, addr).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_to<A: Into<SocketAddr>>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        let addr = addr.into();
+        self.write_with(|io| io.send_to(buf, addr)).await
+    }
+
+    /// Receives a single datagram message from the connected peer.
+    ///
+    /// Returns the number of bytes read.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// The [`connect()`][`Async::<UdpSocket>::connect()`] constructor creates a socket connected to
+    /// a remote address. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let local = ([127, 0, 0, 1], 8000);
+    /// let socket = Async::<UdpSocket>::connect(local, ([127, 0, 0, 1], 9000)).await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = socket.recv(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| or_pending_error(io.recv(buf), || io.take_error()))
+            .await
+    }
+
+    /// Receives a single datagram message from the connected peer without removing it from the
+    /// queue.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// The [`connect()`][`Async::<UdpSocket>::connect()`] constructor creates a socket connected to
+    /// a remote address. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let local = ([127, 0, 0, 1], 8000);
+    /// let socket = Async::<UdpSocket>::connect(local, ([127, 0, 0, 1], 9000)).await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = socket.peek(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| or_pending_error(io.peek(buf), || io.take_error()))
+            .await
+    }
+
+    /// Sends data to the connected peer.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// The [`connect()`][`Async::<UdpSocket>::connect()`] constructor creates a socket connected to
+    /// a remote address. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let local = ([127, 0, 0, 1], 8000);
+    /// let socket = Async::<UdpSocket>::connect(local, ([127, 0, 0, 1], 9000)).await?;
+    ///
+    /// let msg = b"hello";
+    /// let len = socket.send(msg).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|io| io.send(buf)).await
+    }
+
+    /// Receives multiple datagram messages, one into each buffer.
+    ///
+    /// Waits until at least one datagram is queued and then receives as many as are available,
+    /// up to the number of buffers. Returns the number of bytes read and the address every
+    /// datagram came from, in the order the buffers were filled.
+    ///
+    /// Each buffer must be of sufficient size to hold a message. If a message is too long to fit,
+    /// excess bytes may get discarded.
+    ///
+    /// On Linux and Android, the whole batch is received with a single `recvmmsg()` call. On
+    /// other platforms, this method calls `recv_from()` in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut bufs = vec![[0u8; 1500]; 32];
+    /// let mut bufs: Vec<&mut [u8]> = bufs.iter_mut().map(|b| &mut b[..]).collect();
+    /// for (len, addr) in socket.recv_batch(&mut bufs).await? {
+    ///     println!("Received {} bytes from {}", len, addr);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_batch(&self, bufs: &mut [&mut [u8]]) -> io::Result<Vec<(usize, SocketAddr)>> {
+        if bufs.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.read_with(|io| {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let res = batch::recv_batch(io.as_raw_fd(), bufs)?
+                .into_iter()
+                .map(|(len, addr)| Ok((len, inet_addr(&addr)?)))
+                .collect();
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let res = recv_loop(bufs, |buf| io.recv_from(buf));
+            res
+        })
+        .await
+    }
+
+    /// Sends multiple datagrams, each to its own address.
+    ///
+    /// Waits until the socket is writable and then sends as many datagrams as possible without
+    /// blocking. Returns the number of datagrams sent, which may be less than the number of
+    /// messages if the send buffer fills up.
+    ///
+    /// On Linux and Android, the whole batch is sent with a single `sendmmsg()` call. On other
+    /// platforms, this method calls `send_to()` in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::{SocketAddr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// let addr = SocketAddr::from(([127, 0, 0, 1], 9000));
+    ///
+    /// let msgs = [(&b"hello"[..], addr), (&b"world"[..], addr)];
+    /// let sent = socket.send_batch(&msgs).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_batch(&self, msgs: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        if msgs.is_empty() {
+            return Ok(0);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let msgs: Vec<_> = msgs
+            .iter()
+            .map(|(buf, addr)| (*buf, Some(socket2::SockAddr::from(*addr))))
+            .collect();
+        self.write_with(|io| {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let res = batch::send_batch(io.as_raw_fd(), &msgs);
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let res = send_loop(msgs, |(buf, addr)| io.send_to(buf, addr));
+            res
+        })
+        .await
+    }
+
+    /// Sends a buffer split into datagrams of `segment_size` bytes each.
+    ///
+    /// The last datagram may be shorter than `segment_size`. Returns the number of bytes written,
+    /// which is the length of the buffer unless an error occurs after some datagrams were sent.
+    /// In that case the number of bytes sent so far is returned and the error is dropped.
+    ///
+    /// On Linux and Android, the kernel splits the buffer with generic segmentation offload
+    /// (`UDP_SEGMENT`) so that many datagrams cost a single syscall. If the kernel or the network
+    /// device doesn't support it, or on other platforms, the buffer is split in software and sent
+    /// with one `send_to()` call per datagram.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::{SocketAddr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// let addr = SocketAddr::from(([127, 0, 0, 1], 9000));
+    ///
+    /// // Sends 10 datagrams of 1200 bytes each.
+    /// let buf = vec![0u8; 12000];
+    /// socket.send_segments(&buf, 1200, addr).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_segments<A: Into<SocketAddr>>(
+        &self,
+        buf: &[u8],
+        segment_size: usize,
+        addr: A,
+    ) -> io::Result<usize> {
+        let addr = addr.into();
+        if segment_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "segment size must be non-zero",
+            ));
+        }
+
+        let mut sent = 0;
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            if segment_size <= batch::MAX_UDP_PAYLOAD && batch::gso_supported() {
+                let sockaddr = socket2::SockAddr::from(addr);
+                let segments = (batch::MAX_UDP_PAYLOAD / segment_size).min(batch::MAX_GSO_SEGMENTS);
+                let chunk_size = segment_size * segments;
+
+                while sent < buf.len() {
+                    let chunk = &buf[sent..buf.len().min(sent + chunk_size)];
+                    let res = self
+                        .write_with(|io| {
+                            batch::send_segments(
+                                io.as_raw_fd(),
+                                chunk,
+                                segment_size as u16,
+                                &sockaddr,
+                            )
+                        })
+                        .await;
+
+                    match res {
+                        Ok(n) => sent += n,
+                        // The network device can't offload segmentation, e.g. because it doesn't
+                        // compute checksums, so split the rest of the buffer in software.
+                        Err(err) if err.raw_os_error() == Some(libc::EIO) => break,
+                        Err(_) if sent > 0 => return Ok(sent),
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        }
+
+        for segment in buf[sent..].chunks(segment_size) {
+            match self.send_to(segment, addr).await {
+                Ok(n) => sent += n,
+                Err(_) if sent > 0 => return Ok(sent),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Enables or disables generic receive offload (`UDP_GRO`).
+    ///
+    /// With receive offload enabled, the kernel may coalesce consecutive datagrams from the same
+    /// sender into a single buffer, which can be received with
+    /// [`recv_coalesced()`][`Async::<UdpSocket>::recv_coalesced()`].
+    ///
+    /// Returns `false` if the OS doesn't support receive offload, in which case datagrams are
+    /// always received one at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// let enabled = socket.set_gro(true)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_gro(&self, on: bool) -> io::Result<bool> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let res = match sys::setsockopt(
+            self.source.raw,
+            libc::SOL_UDP,
+            libc::UDP_GRO,
+            on as libc::c_int,
+        ) {
+            Ok(()) => Ok(true),
+            Err(err) if err.raw_os_error() == Some(libc::ENOPROTOOPT) => Ok(false),
+            Err(err) => Err(err),
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let res = {
+            let _ = on;
+            Ok(false)
+        };
+        res
+    }
+
+    /// Receives datagrams that may have been coalesced by generic receive offload.
+    ///
+    /// Returns the number of bytes read, the size of the coalesced segments, and the address the
+    /// datagrams came from. Every segment except possibly the last one is exactly
+    /// segment-size bytes long. If the datagram was not coalesced, the segment size equals the
+    /// number of bytes read.
+    ///
+    /// The buffer should be large enough to hold a coalesced batch, up to 64 KiB.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// socket.set_gro(true)?;
+    ///
+    /// let mut buf = vec![0u8; 65536];
+    /// let (len, segment_size, addr) = socket.recv_coalesced(&mut buf).await?;
+    /// for datagram in buf[..len].chunks(segment_size) {
+    ///     println!("Received {} bytes from {}", datagram.len(), addr);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_coalesced(&self, buf: &mut [u8]) -> io::Result<(usize, usize, SocketAddr)> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let (len, segment_size, addr) = {
+            let (len, segment_size, addr) = self
+                .read_with(|io| batch::recv_coalesced(io.as_raw_fd(), buf))
+                .await?;
+            (len, segment_size, inet_addr(&addr)?)
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let (len, segment_size, addr) = {
+            let (len, addr) = self.recv_from(buf).await?;
+            (len, None, addr)
+        };
+        Ok((len, segment_size.unwrap_or(len).max(1), addr))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Async<UdpSocket> {
+    /// Enables or disables receiving the destination address and interface of datagrams.
+    ///
+    /// When enabled, [`Async::<UdpSocket>::recv_msg()`] reports a
+    /// [`ControlMessage::PacketInfo`] for every datagram, which is useful to find out which local
+    /// address a datagram was sent to when the socket is bound to a wildcard address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([0, 0, 0, 0], 0))?;
+    /// socket.set_recv_packet_info(true)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_recv_packet_info(&self, on: bool) -> io::Result<()> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            self.set_flag(libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO, on)
+        } else {
+            self.set_flag(libc::IPPROTO_IP, libc::IP_PKTINFO, on)
+        }
+    }
+
+    /// Enables or disables receiving the type-of-service or traffic class field of datagrams.
+    ///
+    /// When enabled, [`Async::<UdpSocket>::recv_msg()`] reports a [`ControlMessage::Tos`] for
+    /// IPv4 sockets or a [`ControlMessage::TrafficClass`] for IPv6 sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_recv_tos(true)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_recv_tos(&self, on: bool) -> io::Result<()> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            self.set_flag(libc::IPPROTO_IPV6, libc::IPV6_RECVTCLASS, on)
+        } else {
+            self.set_flag(libc::IPPROTO_IP, libc::IP_RECVTOS, on)
+        }
+    }
+
+    /// Enables or disables receiving the time-to-live or hop limit field of datagrams.
+    ///
+    /// When enabled, [`Async::<UdpSocket>::recv_msg()`] reports a [`ControlMessage::Ttl`] for
+    /// IPv4 sockets or a [`ControlMessage::HopLimit`] for IPv6 sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_recv_ttl(true)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_recv_ttl(&self, on: bool) -> io::Result<()> {
+        if self.get_ref().local_addr()?.is_ipv6() {
+            self.set_flag(libc::IPPROTO_IPV6, libc::IPV6_RECVHOPLIMIT, on)
+        } else {
+            self.set_flag(libc::IPPROTO_IP, libc::IP_RECVTTL, on)
+        }
+    }
+
+    /// Enables or disables receiving the time at which datagrams arrived.
+    ///
+    /// When enabled, [`Async::<UdpSocket>::recv_msg()`] reports a [`ControlMessage::Timestamp`]
+    /// with nanosecond precision (`SO_TIMESTAMPNS`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_recv_timestamps(true)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_recv_timestamps(&self, on: bool) -> io::Result<()> {
+        self.set_flag(libc::SOL_SOCKET, libc::SO_TIMESTAMPNS, on)
+    }
+
+    /// Receives a single datagram message into multiple buffers along with control messages.
+    ///
+    /// Returns the number of bytes read and the address the message came from. Control messages
+    /// are stored into `ancillary`, replacing its previous contents.
+    ///
+    /// Which control messages get delivered depends on socket options like
+    /// [`set_recv_packet_info()`][`Async::<UdpSocket>::set_recv_packet_info()`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, ControlMessage, RecvAncillary};
+    /// use std::io::IoSliceMut;
+    /// use std::net::UdpSocket;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([0, 0, 0, 0], 8000))?;
+    /// socket.set_recv_packet_info(true)?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let mut ancillary = RecvAncillary::new();
+    /// let (len, addr) = socket
+    ///     .recv_msg(&mut [IoSliceMut::new(&mut buf)], &mut ancillary)
+    ///     .await?;
+    ///
+    /// for msg in ancillary.messages() {
+    ///     if let ControlMessage::PacketInfo(info) = msg {
+    ///         println!("{} bytes from {} sent to {}", len, addr, info.addr);
+    ///     }
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_msg(
+        &self,
+        bufs: &mut [IoSliceMut<'_>],
+        ancillary: &mut RecvAncillary,
+    ) -> io::Result<(usize, SocketAddr)> {
+        let received = self
+            .read_with(|io| cmsg::recvmsg(io.as_raw_fd(), bufs, ancillary.as_raw(), 0))
+            .await?;
+        ancillary.set_received(received.control_len, received.flags & libc::MSG_CTRUNC != 0);
+
+        Ok((received.len, inet_addr(&received.addr)?))
+    }
+
+    /// Sends data from multiple buffers along with control messages.
+    ///
+    /// If `addr` is [`None`], the datagram is sent to the connected peer.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, SendAncillary};
+    /// use std::io::IoSlice;
+    /// use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([0, 0, 0, 0], 0))?;
+    /// let addr = SocketAddr::from(([127, 0, 0, 1], 9000));
+    ///
+    /// let mut ancillary = SendAncillary::new();
+    /// ancillary.set_source_v4(Ipv4Addr::LOCALHOST, 0);
+    ///
+    /// let bufs = [IoSlice::new(b"hello "), IoSlice::new(b"world")];
+    /// let len = socket.send_msg(&bufs, &ancillary, Some(addr)).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_msg(
+        &self,
+        bufs: &[IoSlice<'_>],
+        ancillary: &SendAncillary,
+        addr: Option<SocketAddr>,
+    ) -> io::Result<usize> {
+        let addr = addr.map(socket2::SockAddr::from);
+        let (control, control_len) = cmsg::encode(ancillary.messages());
+        self.write_with(|io| {
+            cmsg::sendmsg(
+                io.as_raw_fd(),
+                bufs,
+                &control,
+                control_len,
+                addr.as_ref(),
+                0,
+            )
+        })
+        .await
+    }
+
+    /// Turns a boolean socket option on or off.
+    fn set_flag(&self, level: libc::c_int, name: libc::c_int, on: bool) -> io::Result<()> {
+        sys::setsockopt(self.source.raw, level, name, on as libc::c_int)
+    }
+}
+
+#[cfg(unix)]
+impl Async<UnixListener> {
+    /// Creates a UDS listener bound to the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
+    /// println!("Listening on {:?}", listener.get_ref().local_addr()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixListener>> {
+        let path = path.as_ref().to_owned();
+        Async::new(UnixListener::bind(path)?)
+    }
+
+    /// Creates a UDS listener bound to the specified name in the abstract namespace.
+    ///
+    /// Abstract socket addresses don't exist in the filesystem, so they never collide with stale
+    /// files and need no cleanup. The name must not include the leading NUL byte.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixListener>::bind_abstract("socket")?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Async<UnixListener>> {
+        let socket = Socket::new(Domain::unix(), Type::stream(), None)?;
+        socket.bind(&abstract_addr(name.as_ref())?)?;
+        socket.listen(128)?;
+        Async::new(socket.into_unix_listener())
+    }
+
+    /// Accepts a new incoming UDS stream connection.
+    ///
+    /// When a connection is established, it will be returned as a stream together with its remote
+    /// address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
+    /// let (stream, addr) = listener.accept().await?;
+    /// println!("Accepted client: {:?}", addr);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn accept(&self) -> io::Result<(Async<UnixStream>, UnixSocketAddr)> {
+        let (stream, addr) = self.read_with(|io| io.accept()).await?;
+        Ok((Async::new(stream)?, addr))
+    }
+
+    /// Returns a stream of incoming UDS connections.
+    ///
+    /// The stream is infinite, i.e. it never stops with a [`None`] item.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use futures::prelude::*;
+    /// use std::os::unix::net::UnixListener;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixListener>::bind("/tmp/socket")?;
+    /// let mut incoming = listener.incoming();
+    ///
+    /// while let Some(stream) = incoming.next().await {
+    ///     let stream = stream?;
+    ///     println!("Accepted client: {:?}", stream.get_ref().peer_addr()?);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn incoming(
+        &self,
+    ) -> impl Stream<Item = io::Result<Async<UnixStream>>> + Send + Unpin + '_ {
+        Box::pin(stream::unfold(self, |listener| async move {
+            let res = listener.accept().await.map(|(stream, _)| stream);
+            Some((res, listener))
+        }))
+    }
+}
+
+#[cfg(unix)]
+impl Async<UnixStream> {
+    /// Creates a UDS stream connected to the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let stream = Async::<UnixStream>::connect("/tmp/socket").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixStream>> {
+        Async::<UnixStream>::connect_addr(socket2::SockAddr::unix(path)?).await
+    }
+
+    /// Creates a UDS stream connected to the specified name in the abstract namespace.
+    ///
+    /// The name must not include the leading NUL byte.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let stream = Async::<UnixStream>::connect_abstract("socket").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn connect_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Async<UnixStream>> {
+        Async::<UnixStream>::connect_addr(abstract_addr(name.as_ref())?).await
+    }
+
+    /// Creates a UDS stream connected to the specified socket address.
+    async fn connect_addr(addr: socket2::SockAddr) -> io::Result<Async<UnixStream>> {
+        // Create a socket.
+        let socket = Socket::new(Domain::unix(), Type::stream(), None)?;
+
+        // Begin async connect and ignore the inevitable "in progress" error.
+        socket.set_nonblocking(true)?;
+        socket.connect(&addr).or_else(|err| {
+            if err.raw_os_error() == Some(libc::EINPROGRESS) {
+                Ok(())
+            } else {
+                Err(err)
+            }
+        })?;
+        let stream = Async::new(socket.into_unix_stream())?;
+
+        // The stream becomes writable when connected.
+        stream.writable().await?;
+
+        Ok(stream)
+    }
+
+    /// Creates an unnamed pair of connected UDS stream sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn pair() -> io::Result<(Async<UnixStream>, Async<UnixStream>)> {
+        let (stream1, stream2) = UnixStream::pair()?;
+        Ok((Async::new(stream1)?, Async::new(stream2)?))
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// Tasks blocked reading from or writing to the shut down halves are woken up, so that they
+    /// observe end of stream or a "broken pipe" error right away.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::net::Shutdown;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    /// stream1.shutdown(Shutdown::Read)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.get_ref().shutdown(how)?;
+        self.source.wake(
+            how == Shutdown::Read || how == Shutdown::Both,
+            how == Shutdown::Write || how == Shutdown::Both,
+        );
+        Ok(())
+    }
+
+    /// Returns the credentials of the process on the other end of this stream.
+    ///
+    /// The credentials are those the peer had when it called `connect()` or `socketpair()`. The
+    /// process ID is only reported on Linux and Android.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    /// let cred = stream1.peer_cred()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        cred::peer_cred(self.source.raw)
+    }
+
+    /// Sends data along with file descriptors to the connected peer.
+    ///
+    /// The file descriptors are transferred with an `SCM_RIGHTS` control message and stay open
+    /// in this process. Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    ///
+    /// let file = File::open("/etc/hosts")?;
+    /// let len = stream1.send_with_fds(b"hosts", &[file.as_raw_fd()]).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        self.write_with(|io| cmsg::send_fds(io.as_raw_fd(), buf, fds))
+            .await
+    }
+
+    /// Receives data along with up to `max_fds` file descriptors from the connected peer.
+    ///
+    /// Returns the number of bytes read and the received file descriptors as [`File`]s, which
+    /// have the close-on-exec flag set.
+    ///
+    /// If the peer sent more than `max_fds` file descriptors, the data is still returned along
+    /// with the first `max_fds` of them, and the rest are closed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixStream;
+    ///
+    /// # blocking::block_on(async {
+    /// let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, fds) = stream2.recv_with_fds(&mut buf, 4).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_with_fds(
+        &self,
+        buf: &mut [u8],
+        max_fds: usize,
+    ) -> io::Result<(usize, Vec<File>)> {
+        self.read_with(|io| cmsg::recv_fds(io.as_raw_fd(), buf, max_fds))
+            .await
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+impl Async<UnixSeqpacketListener> {
+    /// Creates a `SOCK_SEQPACKET` listener bound to the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacketListener};
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixSeqpacketListener>::bind("/tmp/socket")?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixSeqpacketListener>> {
+        Async::new(UnixSeqpacketListener::bind(path)?)
+    }
+
+    /// Accepts a new incoming `SOCK_SEQPACKET` connection.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacketListener};
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixSeqpacketListener>::bind("/tmp/socket")?;
+    /// let conn = listener.accept().await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn accept(&self) -> io::Result<Async<UnixSeqpacket>> {
+        let conn = self.read_with(|io| io.accept()).await?;
+        Async::new(conn)
+    }
+
+    /// Returns a stream of incoming `SOCK_SEQPACKET` connections.
+    ///
+    /// The stream is infinite, i.e. it never stops with a [`None`] item.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacketListener};
+    /// use futures::prelude::*;
+    ///
+    /// # blocking::block_on(async {
+    /// let listener = Async::<UnixSeqpacketListener>::bind("/tmp/socket")?;
+    /// let mut incoming = listener.incoming();
+    ///
+    /// while let Some(conn) = incoming.next().await {
+    ///     let conn = conn?;
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn incoming(
+        &self,
+    ) -> impl Stream<Item = io::Result<Async<UnixSeqpacket>>> + Send + Unpin + '_ {
+        Box::pin(stream::unfold(self, |listener| async move {
+            Some((listener.accept().await, listener))
+        }))
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+impl Async<UnixSeqpacket> {
+    /// Creates a `SOCK_SEQPACKET` connection to the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacket};
+    ///
+    /// # blocking::block_on(async {
+    /// let conn = Async::<UnixSeqpacket>::connect("/tmp/socket").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixSeqpacket>> {
+        // Create a socket.
+        let socket = Socket::new(Domain::unix(), Type::seqpacket(), None)?;
+
+        // Begin async connect and ignore the inevitable "in progress" error.
+        socket.set_nonblocking(true)?;
+        socket
+            .connect(&socket2::SockAddr::unix(path)?)
+            .or_else(|err| {
+                if err.raw_os_error() == Some(libc::EINPROGRESS) {
+                    Ok(())
+                } else {
+                    Err(err)
+                }
+            })?;
+        let conn = Async::new(UnixSeqpacket::from_socket(socket))?;
+
+        // The socket becomes writable when connected.
+        conn.writable().await?;
+
+        // Check if there was an error while connecting.
+        match conn.get_ref().take_error()? {
+            None => Ok(conn),
+            Some(err) => Err(err),
+        }
+    }
+
+    /// Creates an unnamed pair of connected `SOCK_SEQPACKET` sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::{Async, UnixSeqpacket};
+    ///
+    /// # blocking::block_on(async {
+    /// let (conn1, conn2) = Async::<UnixSeqpacket>::pair()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn pair() -> io::Result<(Async<UnixSeqpacket>, Async<UnixSeqpacket>)> {
+        let (conn1, conn2) = UnixSeqpacket::pair()?;
+        Ok((Async::new(conn1)?, Async::new(conn2)?))
+    }
+
+    /// Receives a message from the connected peer.
+    ///
+    /// Returns the number of bytes read. If the message doesn't fit into the buffer, the rest of
+    /// it is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacket};
+    ///
+    /// # blocking::block_on(async {
+    /// let conn = Async::<UnixSeqpacket>::connect("/tmp/socket").await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = conn.recv(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| io.recv(buf)).await
+    }
+
+    /// Sends a message to the connected peer.
+    ///
+    /// Returns the number of bytes written. The message is sent as a whole or not at all.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UnixSeqpacket};
+    ///
+    /// # blocking::block_on(async {
+    /// let conn = Async::<UnixSeqpacket>::connect("/tmp/socket").await?;
+    ///
+    /// let msg = b"hello";
+    /// let len = conn.send(msg).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|io| io.send(buf)).await
+    }
+}
+
+#[cfg(unix)]
+impl Async<UnixDatagram> {
+    /// Creates a UDS datagram socket bound to the specified path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket")?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixDatagram>> {
+        let path = path.as_ref().to_owned();
+        Async::new(UnixDatagram::bind(path)?)
+    }
+
+    /// Creates a UDS datagram socket bound to the specified name in the abstract namespace.
+    ///
+    /// The name must not include the leading NUL byte.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::bind_abstract("socket")?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_abstract<N: AsRef<[u8]>>(name: N) -> io::Result<Async<UnixDatagram>> {
+        let socket = Socket::new(Domain::unix(), Type::dgram(), None)?;
+        socket.bind(&abstract_addr(name.as_ref())?)?;
+        Async::new(socket.into_unix_datagram())
+    }
+
+    /// Creates a UDS datagram socket not bound to any address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::unbound()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn unbound() -> io::Result<Async<UnixDatagram>> {
+        Async::new(UnixDatagram::unbound()?)
+    }
+
+    /// Creates an unbound UDS datagram socket connected to the specified path.
+    ///
+    /// A connected socket can use [`recv()`][`Async::<UnixDatagram>::recv()`] and
+    /// [`send()`][`Async::<UnixDatagram>::send()`]. Once the peer socket is closed, sends fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::connect("/tmp/socket").await?;
+    /// socket.send(b"hello").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Async<UnixDatagram>> {
+        let socket = Async::<UnixDatagram>::unbound()?;
+        socket.get_ref().connect(path)?;
+        Ok(socket)
+    }
+
+    /// Creates an unnamed pair of connected Unix datagram sockets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn pair() -> io::Result<(Async<UnixDatagram>, Async<UnixDatagram>)> {
+        let (socket1, socket2) = UnixDatagram::pair()?;
+        Ok((Async::new(socket1)?, Async::new(socket2)?))
+    }
+
+    /// Receives data from the socket.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::bind("/tmp/socket")?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
+        self.read_with(|io| io.recv_from(buf)).await
+    }
+
+    /// Sends data to the specified address.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::unbound()?;
+    ///
+    /// let msg = b"hello";
+    /// let addr = "/tmp/socket";
+    /// let len = socket.send_to(msg, addr).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> io::Result<usize> {
+        self.write_with(|io| io.send_to(buf, &path)).await
+    }
+
+    /// Sends data to the specified name in the abstract namespace.
+    ///
+    /// The name must not include the leading NUL byte. Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::unbound()?;
+    ///
+    /// let msg = b"hello";
+    /// let len = socket.send_to_abstract(msg, "socket").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn send_to_abstract<N: AsRef<[u8]>>(&self, buf: &[u8], name: N) -> io::Result<usize> {
+        let addr = abstract_addr(name.as_ref())?;
+        self.write_with(|io| sys::socket_ref(io.as_raw_fd()).send_to(buf, &addr))
+            .await
+    }
+
+    /// Receives data from the connected peer.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// The [`connect()`][`Async::<UnixDatagram>::connect()`] constructor creates a socket connected
+    /// to a remote address. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::connect("/tmp/socket").await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = socket.recv(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| io.recv(buf)).await
+    }
+
+    /// Sends data to the connected peer.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// The [`connect()`][`Async::<UnixDatagram>::connect()`] constructor creates a socket connected
+    /// to a remote address. This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<UnixDatagram>::connect("/tmp/socket").await?;
+    ///
+    /// let msg = b"hello";
+    /// let len = socket.send(msg).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|io| io.send(buf)).await
+    }
+
+    /// Receives multiple datagram messages from the connected peer, one into each buffer.
+    ///
+    /// Waits until at least one datagram is queued and then receives as many as are available,
+    /// up to the number of buffers. Returns the number of bytes read into every buffer that was
+    /// filled, in order.
+    ///
+    /// On Linux and Android, the whole batch is received with a single `recvmmsg()` call. On
+    /// other platforms, this method calls `recv()` in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    ///
+    /// let mut bufs = vec![[0u8; 1024]; 16];
+    /// let mut bufs: Vec<&mut [u8]> = bufs.iter_mut().map(|b| &mut b[..]).collect();
+    /// let lens = socket2.recv_batch(&mut bufs).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_batch(&self, bufs: &mut [&mut [u8]]) -> io::Result<Vec<usize>> {
+        if bufs.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.read_with(|io| {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let res = batch::recv_batch(io.as_raw_fd(), bufs)
+                .map(|received| received.into_iter().map(|(len, _)| len).collect());
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let res = recv_loop(bufs, |buf| io.recv(buf));
+            res
+        })
+        .await
+    }
+
+    /// Sends multiple datagrams to the connected peer.
+    ///
+    /// Waits until the socket is writable and then sends as many datagrams as possible without
+    /// blocking. Returns the number of datagrams sent, which may be less than the number of
+    /// buffers if the send buffer fills up.
+    ///
+    /// On Linux and Android, the whole batch is sent with a single `sendmmsg()` call. On other
+    /// platforms, this method calls `send()` in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    ///
+    /// let sent = socket1.send_batch(&[b"hello", b"world"]).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_batch(&self, bufs: &[&[u8]]) -> io::Result<usize> {
+        if bufs.is_empty() {
+            return Ok(0);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let msgs: Vec<_> = bufs.iter().map(|buf| (*buf, None)).collect();
+        self.write_with(|io| {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let res = batch::send_batch(io.as_raw_fd(), &msgs);
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let res = send_loop(bufs, |buf| io.send(buf));
+            res
+        })
+        .await
+    }
+
+    /// Enables or disables receiving the credentials of senders (`SO_PASSCRED`).
+    ///
+    /// When enabled, [`recv_with_cred()`][`Async::recv_with_cred()`] reports the
+    /// credentials of the process that sent each message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// let socket = Async::<UnixDatagram>::unbound()?;
+    /// socket.set_pass_cred(true)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_pass_cred(&self, on: bool) -> io::Result<()> {
+        sys::setsockopt(
+            self.source.raw,
+            libc::SOL_SOCKET,
+            libc::SO_PASSCRED,
+            on as libc::c_int,
+        )
+    }
+
+    /// Sends data along with credentials to the connected peer.
+    ///
+    /// The credentials are transferred with an `SCM_CREDENTIALS` control message. The kernel
+    /// checks them: unprivileged processes may only send their own IDs, as returned by
+    /// [`UCred::current()`]. A missing process ID is replaced with that of the current process.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::{Async, UCred};
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    ///
+    /// let len = socket1.send_with_cred(b"hello", &UCred::current()).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn send_with_cred(&self, buf: &[u8], cred: &UCred) -> io::Result<usize> {
+        self.write_with(|io| cred::send_cred(io.as_raw_fd(), buf, cred))
+            .await
+    }
+
+    /// Receives data along with the credentials of the sender from the connected peer.
+    ///
+    /// Returns the number of bytes read and the sender's credentials. Credentials are only
+    /// reported if [`set_pass_cred()`][`Async::set_pass_cred()`] was enabled
+    /// before the message was sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    /// socket2.set_pass_cred(true)?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, cred) = socket2.recv_with_cred(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn recv_with_cred(&self, buf: &mut [u8]) -> io::Result<(usize, Option<UCred>)> {
+        self.read_with(|io| cred::recv_cred(io.as_raw_fd(), buf))
+            .await
+    }
+
+    /// Sends data along with file descriptors to the connected peer.
+    ///
+    /// The file descriptors are transferred with an `SCM_RIGHTS` control message and stay open
+    /// in this process. Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    ///
+    /// let file = File::open("/etc/hosts")?;
+    /// let len = socket1.send_with_fds(b"hosts", &[file.as_raw_fd()]).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        self.write_with(|io| cmsg::send_fds(io.as_raw_fd(), buf, fds))
+            .await
+    }
+
+    /// Receives data along with up to `max_fds` file descriptors from the connected peer.
+    ///
+    /// Returns the number of bytes read and the received file descriptors as [`File`]s, which
+    /// have the close-on-exec flag set.
+    ///
+    /// If the peer sent more than `max_fds` file descriptors, the data is still returned along
+    /// with the first `max_fds` of them, and the rest are closed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::Async;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// # blocking::block_on(async {
+    /// let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, fds) = socket2.recv_with_fds(&mut buf, 4).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_with_fds(
+        &self,
+        buf: &mut [u8],
+        max_fds: usize,
+    ) -> io::Result<(usize, Vec<File>)> {
+        self.read_with(|io| cmsg::recv_fds(io.as_raw_fd(), buf, max_fds))
+            .await
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Async<EventFd> {
+    /// Adds `n` to the counter, waking tasks waiting for it.
+    ///
+    /// This never blocks, so it can be called from threads outside the async runtime. If the
+    /// counter would exceed `u64::MAX - 1`, an error of kind [`io::ErrorKind::WouldBlock`] is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::eventfd::EventFd;
+    /// use async_io::Async;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// # blocking::block_on(async {
+    /// let event = Arc::new(Async::new(EventFd::new(0)?)?);
+    ///
+    /// let notifier = event.clone();
+    /// thread::spawn(move || notifier.notify(1));
+    ///
+    /// assert_eq!(event.wait().await?, 1);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn notify(&self, n: u64) -> io::Result<()> {
+        self.get_ref().notify(n)
+    }
+
+    /// Waits until the counter is non-zero and reads it.
+    ///
+    /// In counter mode, returns the value of the counter and resets it to zero. In semaphore
+    /// mode, returns 1 and decrements the counter by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::eventfd::EventFd;
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let event = Async::new(EventFd::semaphore(2)?)?;
+    ///
+    /// assert_eq!(event.wait().await?, 1);
+    /// assert_eq!(event.wait().await?, 1);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn wait(&self) -> io::Result<u64> {
+        self.read_with(|io| io.read()).await
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Async<TimerFd> {
+    /// Arms the timer to fire after `initial` and then every `interval`, if set.
+    ///
+    /// A zero `initial` duration fires the timer right away. Arming the timer again replaces the
+    /// previous settings and resets the expiration count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::timerfd::{Clock, TimerFd};
+    /// use async_io::Async;
+    /// use std::time::Duration;
+    ///
+    /// # blocking::block_on(async {
+    /// let timer = Async::new(TimerFd::new(Clock::Monotonic)?)?;
+    /// timer.set(Duration::from_millis(10), None)?;
+    /// timer.wait().await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set(&self, initial: Duration, interval: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set(initial, interval)
+    }
+
+    /// Disarms the timer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::timerfd::{Clock, TimerFd};
+    /// use async_io::Async;
+    /// use std::time::Duration;
+    ///
+    /// # blocking::block_on(async {
+    /// let timer = Async::new(TimerFd::new(Clock::Monotonic)?)?;
+    /// timer.set(Duration::from_secs(60), None)?;
+    /// timer.disarm()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn disarm(&self) -> io::Result<()> {
+        self.get_ref().disarm()
+    }
+
+    /// Waits until the timer fires and returns the number of expirations since it was armed or
+    /// last waited for.
+    ///
+    /// Expirations are counted by the kernel, so none are lost if the timer fires several times
+    /// before this function is called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::timerfd::{Clock, TimerFd};
+    /// use async_io::Async;
+    /// use std::time::Duration;
+    ///
+    /// # blocking::block_on(async {
+    /// let timer = Async::new(TimerFd::new(Clock::Monotonic)?)?;
+    /// timer.set(Duration::from_millis(10), None)?;
+    /// assert_eq!(timer.wait().await?, 1);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn wait(&self) -> io::Result<u64> {
+        self.read_with(|io| io.read()).await
+    }
+
+    /// Returns a stream of expiration counts.
+    ///
+    /// Every item is the number of expirations since the previous item. The stream is infinite,
+    /// i.e. it never stops with a [`None`] item, but it only yields while the timer is armed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::timerfd::{Clock, TimerFd};
+    /// use async_io::Async;
+    /// use futures_lite::*;
+    /// use std::time::Duration;
+    ///
+    /// # blocking::block_on(async {
+    /// let timer = Async::new(TimerFd::new(Clock::Monotonic)?)?;
+    /// timer.set(Duration::from_millis(10), Some(Duration::from_millis(10)))?;
+    ///
+    /// let mut expirations = timer.expirations();
+    /// while let Some(count) = expirations.next().await {
+    ///     println!("fired {} times", count?);
+    /// #   break;
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn expirations(&self) -> impl Stream<Item = io::Result<u64>> + Send + Unpin + '_ {
+        Box::pin(stream::unfold(self, |timer| async move {
+            Some((timer.wait().await, timer))
+        }))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Async<Inotify> {
+    /// Creates an `inotify` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::inotify::Inotify;
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let inotify = Async::<Inotify>::init()?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn init() -> io::Result<Async<Inotify>> {
+        Async::new(Inotify::init()?)
+    }
+
+    /// Starts watching a file or directory for the events in `mask`.
+    ///
+    /// Watching a path that is already watched replaces its mask and returns the same watch
+    /// descriptor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::inotify::{Inotify, WatchMask};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let inotify = Async::<Inotify>::init()?;
+    /// let wd = inotify.add_watch("/etc/app.conf", WatchMask::CLOSE_WRITE)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn add_watch<P: AsRef<Path>>(
+        &self,
+        path: P,
+        mask: WatchMask,
+    ) -> io::Result<WatchDescriptor> {
+        self.get_ref().add_watch(path, mask)
+    }
+
+    /// Stops watching.
+    ///
+    /// An event with [`IGNORED`][`WatchMask::IGNORED`] is reported for the removed watch.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::inotify::{Inotify, WatchMask};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let inotify = Async::<Inotify>::init()?;
+    /// let wd = inotify.add_watch("/etc/app.conf", WatchMask::CLOSE_WRITE)?;
+    /// inotify.remove_watch(wd)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn remove_watch(&self, wd: WatchDescriptor) -> io::Result<()> {
+        self.get_ref().remove_watch(wd)
+    }
+
+    /// Returns a stream of filesystem events.
+    ///
+    /// The stream is infinite, i.e. it never stops with a [`None`] item.
+    ///
+    /// If events arrive faster than they are read, the kernel drops them and reports an event
+    /// with [`Q_OVERFLOW`][`WatchMask::Q_OVERFLOW`] and no watch descriptor. Watched files should
+    /// then be rescanned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::inotify::{Inotify, WatchMask};
+    /// use async_io::Async;
+    /// use futures_lite::*;
+    ///
+    /// # blocking::block_on(async {
+    /// let inotify = Async::<Inotify>::init()?;
+    /// inotify.add_watch("/etc/app.conf", WatchMask::CLOSE_WRITE)?;
+    ///
+    /// let mut events = inotify.events();
+    /// while let Some(event) = events.next().await {
+    ///     let event = event?;
+    ///     if event.mask.contains(WatchMask::Q_OVERFLOW) {
+    ///         println!("events were lost");
+    ///     } else {
+    ///         println!("config changed: {:?}", event);
+    ///     }
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn events(&self) -> impl Stream<Item = io::Result<InotifyEvent>> + Send + Unpin + '_ {
+        let queue = std::collections::VecDeque::new();
+        Box::pin(stream::unfold(
+            (self, queue),
+            |(inotify, mut queue)| async move {
+                while queue.is_empty() {
+                    let mut buf = [0u8; inotify::BUF_SIZE];
+                    let res = inotify.read_with(|io| io.read(&mut buf)).await;
+                    match res {
+                        Ok(n) => inotify::parse(&buf[..n], &mut queue),
+                        Err(err) => return Some((Err(err), (inotify, queue))),
+                    }
+                }
+                let event = queue.pop_front().unwrap();
+                Some((Ok(event), (inotify, queue)))
+            },
+        ))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Async<NetlinkSocket> {
+    /// Creates a netlink socket for `protocol` and joins the multicast groups in the `groups` bit
+    /// mask.
+    ///
+    /// Pass 0 as `groups` to only send requests and receive replies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, RTMGRP_IPV4_ROUTE, RTMGRP_LINK};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, RTMGRP_LINK | RTMGRP_IPV4_ROUTE)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind(protocol: i32, groups: u32) -> io::Result<Async<NetlinkSocket>> {
+        let socket = NetlinkSocket::new(protocol)?;
+        socket.bind(groups)?;
+        Async::new(socket)
+    }
+
+    /// Receives a datagram from the kernel.
+    ///
+    /// Datagrams sent by other processes are dropped unless
+    /// [`NetlinkSocket::set_accept_processes()`] enabled them.
+    ///
+    /// A datagram that doesn't fit into the buffer is an error of kind
+    /// [`io::ErrorKind::InvalidData`]. Buffers of 32 KiB fit every datagram the kernel sends.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, RTMGRP_LINK};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, RTMGRP_LINK)?;
+    ///
+    /// let mut buf = vec![0u8; 32768];
+    /// let len = socket.recv(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| io.recv(buf)).await
+    }
+
+    /// Sends a datagram to the kernel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::netlink::{NetlinkMessage, NetlinkSocket, NETLINK_ROUTE};
+    /// use async_io::netlink::{NLM_F_DUMP, NLM_F_REQUEST, RTM_GETLINK};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, 0)?;
+    ///
+    /// let mut msg = NetlinkMessage::new(RTM_GETLINK, NLM_F_REQUEST | NLM_F_DUMP, vec![0; 16]);
+    /// msg.seq = socket.get_ref().next_seq();
+    /// socket.send(&msg.to_bytes()).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|io| io.send(buf)).await
+    }
+
+    /// Sends a request to the kernel and returns its sequence number.
+    ///
+    /// The [`NLM_F_REQUEST`][`netlink::NLM_F_REQUEST`] flag is added to `flags`, and the message
+    /// gets the next sequence number of this socket. Pass the sequence number to
+    /// [`responses()`][`Async::<NetlinkSocket>::responses()`] to receive the reply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, NLM_F_DUMP, RTM_GETADDR};
+    /// use async_io::Async;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, 0)?;
+    ///
+    /// // The payload of the request is a zeroed `struct ifaddrmsg`.
+    /// let seq = socket.request(RTM_GETADDR, NLM_F_DUMP, &[0; 8]).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn request(&self, kind: u16, flags: u16, payload: &[u8]) -> io::Result<u32> {
+        let mut msg = NetlinkMessage::new(kind, flags | netlink::NLM_F_REQUEST, payload);
+        msg.seq = self.get_ref().next_seq();
+
+        // Queue the reply even if it arrives before the stream for it is created, unless sending
+        // fails or is cancelled.
+        let subscription = self.get_ref().subscribe_replies(msg.seq);
+        self.send(&msg.to_bytes()).await?;
+        subscription.keep();
+        Ok(msg.seq)
+    }
+
+    /// Returns a stream of the messages replying to the request with sequence number `seq`.
+    ///
+    /// The stream ends after the last message of the reply. An [`NLMSG_DONE`][`netlink::NLMSG_DONE`]
+    /// message or an acknowledgment ends the stream without being yielded, and an error reported by
+    /// the kernel is yielded as an error and ends the stream.
+    ///
+    /// Replies to requests sent with [`request()`][`Async::<NetlinkSocket>::request()`] are
+    /// queued until their stream is dropped, and the rest of a reply is discarded after that.
+    /// Streams for different requests can be polled concurrently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, NLM_F_DUMP, RTM_GETROUTE};
+    /// use async_io::Async;
+    /// use futures_lite::*;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, 0)?;
+    ///
+    /// // The payload of the request is a zeroed `struct rtmsg`.
+    /// let seq = socket.request(RTM_GETROUTE, NLM_F_DUMP, &[0; 12]).await?;
+    ///
+    /// let mut routes = socket.responses(seq);
+    /// while let Some(route) = routes.next().await {
+    ///     println!("{:?}", route?);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn responses(
+        &self,
+        seq: u32,
+    ) -> impl Stream<Item = io::Result<NetlinkMessage>> + Send + Unpin + '_ {
+        let subscription = self.get_ref().subscribe_replies(seq);
+        Box::pin(stream::unfold(
+            (self, subscription, false),
+            |(socket, subscription, done)| async move {
+                if done {
+                    return None;
+                }
+                let msg = match socket.next_message(subscription.key()).await {
+                    Ok(msg) => msg,
+                    Err(err) => return Some((Err(err), (socket, subscription, true))),
+                };
+                if let Some(err) = msg.error() {
+                    return Some((Err(err), (socket, subscription, true)));
+                }
+                if msg.kind == netlink::NLMSG_DONE || msg.kind == netlink::NLMSG_ERROR {
+                    return None;
+                }
+                let done = msg.flags & netlink::NLM_F_MULTI == 0;
+                Some((Ok(msg), (socket, subscription, done)))
+            },
+        ))
+    }
+
+    /// Returns a stream of notifications and other messages that don't reply to a request.
+    ///
+    /// This is how notifications from the multicast groups joined with
+    /// [`bind()`][`Async::<NetlinkSocket>::bind()`] are received. The stream yields messages
+    /// received after it was created, except replies to requests sent with
+    /// [`request()`][`Async::<NetlinkSocket>::request()`]. Every stream gets its own copy of each
+    /// message, and messages are dropped while no stream exists. The stream is infinite, i.e. it
+    /// never stops with a [`None`] item.
+    ///
+    /// If notifications arrive faster than they are read, they are dropped and the stream yields
+    /// an error with `ENOBUFS`. The state of interest should then be requested again.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, RTMGRP_LINK, RTM_DELLINK, RTM_NEWLINK};
+    /// use async_io::Async;
+    /// use futures_lite::*;
+    ///
+    /// # blocking::block_on(async {
+    /// let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, RTMGRP_LINK)?;
+    ///
+    /// let mut messages = socket.messages();
+    /// while let Some(msg) = messages.next().await {
+    ///     match msg?.kind {
+    ///         RTM_NEWLINK => println!("interface added or changed"),
+    ///         RTM_DELLINK => println!("interface removed"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn messages(&self) -> impl Stream<Item = io::Result<NetlinkMessage>> + Send + Unpin + '_ {
+        let subscription = self.get_ref().subscribe();
+        Box::pin(stream::unfold(
+            (self, subscription),
+            |(socket, subscription)| async move {
+                let res = socket.next_message(subscription.key()).await;
+                Some((res, (socket, subscription)))
+            },
+        ))
+    }
+
+    /// Returns the next message queued for a subscription, skipping no-ops.
+    ///
+    /// While none is queued, receives datagrams and queues their messages for the streams they
+    /// belong to. Any stream may receive the message another stream waits for, so this also
+    /// waits for messages to be queued by others.
+    async fn next_message(&self, key: netlink::Key) -> io::Result<NetlinkMessage> {
+        loop {
+            if let Some(res) = self.get_ref().take(key) {
+                match res {
+                    Ok(msg) if msg.kind == netlink::NLMSG_NOOP => continue,
+                    res => return res,
+                }
+            }
+            match self.get_ref().receive() {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => {
+                    res?;
+                    continue;
+                }
+            }
+
+            let readable = self.readable();
+            pin!(readable);
+            future::poll_fn(|cx| {
+                if self.get_ref().poll_queued(key, cx) {
+                    return Poll::Ready(Ok(()));
+                }
+                readable.as_mut().poll(cx)
+            })
+            .await?;
+        }
+    }
+}
+
+/// Replaces a "would block" error with the pending error of a socket, if there is one.
+///
+/// Connected UDP sockets get errors asynchronously, e.g. "connection refused" caused by an ICMP
+/// message. Some platforms only report them through `SO_ERROR`, which would otherwise leave
+/// a task waiting forever for a datagram that never arrives.
+fn or_pending_error<T>(
+    res: io::Result<T>,
+    take_error: impl FnOnce() -> io::Result<Option<io::Error>>,
+) -> io::Result<T> {
+    match res {
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => match take_error()? {
+            Some(pending) => Err(pending),
+            None => Err(err),
+        },
+        res => res,
+    }
+}
+
+/// Creates a Unix socket address for a name in the abstract namespace.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn abstract_addr(name: &[u8]) -> io::Result<socket2::SockAddr> {
+    let mut addr: libc::sockaddr_un = unsafe { std::mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+    // The first byte of the path stays NUL, which marks the address as abstract.
+    if name.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "abstract socket name is too long",
+        ));
+    }
+    for (dst, src) in addr.sun_path[1..].iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+
+    let len = std::mem::size_of::<libc::sa_family_t>() + 1 + name.len();
+    Ok(unsafe {
+        socket2::SockAddr::from_raw_parts(
+            &addr as *const libc::sockaddr_un as *const libc::sockaddr,
+            len as libc::socklen_t,
+        )
+    })
+}
+
+/// Creates a request to join or leave a source-specific multicast group.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+))]
+fn ip_mreq_source(source: Ipv4Addr, group: Ipv4Addr, interface: Ipv4Addr) -> libc::ip_mreq_source {
+    let in_addr = |addr: Ipv4Addr| libc::in_addr {
+        s_addr: u32::from(addr).to_be(),
+    };
+    libc::ip_mreq_source {
+        imr_multiaddr: in_addr(group),
+        imr_interface: in_addr(interface),
+        imr_sourceaddr: in_addr(source),
+    }
+}
+
+/// Converts a socket address into an internet socket address.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn inet_addr(addr: &socket2::SockAddr) -> io::Result<SocketAddr> {
+    addr.as_std()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid socket address"))
+}
+
+/// Receives datagrams one by one until all buffers are filled or an error occurs.
+///
+/// An error is returned only if not a single datagram was received.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn recv_loop<R>(
+    bufs: &mut [&mut [u8]],
+    mut recv: impl FnMut(&mut [u8]) -> io::Result<R>,
+) -> io::Result<Vec<R>> {
+    let mut received = Vec::new();
+    for buf in bufs.iter_mut() {
+        match recv(buf) {
+            Ok(res) => received.push(res),
+            Err(err) if received.is_empty() => return Err(err),
+            Err(_) => break,
+        }
+    }
+    Ok(received)
+}
+
+/// Sends datagrams one by one until all are sent or an error occurs.
+///
+/// An error is returned only if not a single datagram was sent.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn send_loop<M: Copy>(
+    msgs: &[M],
+    mut send: impl FnMut(M) -> io::Result<usize>,
+) -> io::Result<usize> {
+    let mut sent = 0;
+    for msg in msgs {
+        match send(*msg) {
+            Ok(_) => sent += 1,
+            Err(err) if sent == 0 => return Err(err),
+            Err(_) => break,
+        }
     }
+    Ok(sent)
 }
 
 /// Pins a future and then polls it.
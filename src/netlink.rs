@@ -0,0 +1,669 @@
+//! Netlink sockets for talking to the Linux kernel.
+//!
+//! A [`NetlinkSocket`] sends requests to a kernel subsystem and receives its replies and, after
+//! joining multicast groups, its notifications. With [`NETLINK_ROUTE`], this is how changes to
+//! network interfaces, addresses, and routes are observed.
+//!
+//! Every message starts with a header described by [`NetlinkMessage`]. Requests are numbered with
+//! sequence numbers, and the kernel copies the number of a request into every message of its
+//! reply, while notifications have sequence number 0.
+//!
+//! # Examples
+//!
+//! List network interfaces:
+//!
+//! ```
+//! use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, NLM_F_DUMP, RTM_GETLINK, RTM_NEWLINK};
+//! use async_io::Async;
+//! use futures_lite::*;
+//!
+//! # blocking::block_on(async {
+//! let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, 0)?;
+//!
+//! // The payload of the request is a zeroed `struct ifinfomsg`.
+//! let seq = socket.request(RTM_GETLINK, NLM_F_DUMP, &[0; 16]).await?;
+//!
+//! let mut responses = socket.responses(seq);
+//! while let Some(msg) = responses.next().await {
+//!     assert_eq!(msg?.kind, RTM_NEWLINK);
+//! }
+//! # std::io::Result::Ok(()) });
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Waker};
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+
+use crate::sys;
+
+/// Routing and link information: interfaces, addresses, routes, and neighbors.
+pub const NETLINK_ROUTE: i32 = 0;
+
+/// Notifications about network interfaces, for [`NetlinkSocket::bind()`].
+pub const RTMGRP_LINK: u32 = 0x1;
+
+/// Notifications about IPv4 addresses, for [`NetlinkSocket::bind()`].
+pub const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+
+/// Notifications about IPv4 routes, for [`NetlinkSocket::bind()`].
+pub const RTMGRP_IPV4_ROUTE: u32 = 0x40;
+
+/// Notifications about IPv6 addresses, for [`NetlinkSocket::bind()`].
+pub const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+/// Notifications about IPv6 routes, for [`NetlinkSocket::bind()`].
+pub const RTMGRP_IPV6_ROUTE: u32 = 0x400;
+
+/// A network interface was created or changed, or the reply to [`RTM_GETLINK`].
+pub const RTM_NEWLINK: u16 = 16;
+
+/// A network interface was removed.
+pub const RTM_DELLINK: u16 = 17;
+
+/// Requests network interfaces.
+pub const RTM_GETLINK: u16 = 18;
+
+/// An address was added, or the reply to [`RTM_GETADDR`].
+pub const RTM_NEWADDR: u16 = 20;
+
+/// An address was removed.
+pub const RTM_DELADDR: u16 = 21;
+
+/// Requests addresses.
+pub const RTM_GETADDR: u16 = 22;
+
+/// A route was added, or the reply to [`RTM_GETROUTE`].
+pub const RTM_NEWROUTE: u16 = 24;
+
+/// A route was removed.
+pub const RTM_DELROUTE: u16 = 25;
+
+/// Requests routes.
+pub const RTM_GETROUTE: u16 = 26;
+
+/// A message that carries nothing and is skipped.
+pub const NLMSG_NOOP: u16 = 1;
+
+/// An error, or an acknowledgment if its error code is 0.
+pub const NLMSG_ERROR: u16 = 2;
+
+/// Ends a reply made of multiple messages.
+pub const NLMSG_DONE: u16 = 3;
+
+/// The message is a request.
+///
+/// Set automatically by [`Async::<NetlinkSocket>::request()`][`crate::Async::request()`].
+pub const NLM_F_REQUEST: u16 = 0x1;
+
+/// The message is part of a reply made of multiple messages, ended by [`NLMSG_DONE`].
+pub const NLM_F_MULTI: u16 = 0x2;
+
+/// Asks for an acknowledgment of a request that has no other reply.
+pub const NLM_F_ACK: u16 = 0x4;
+
+/// Asks for all objects of a kind rather than a single one.
+pub const NLM_F_DUMP: u16 = 0x300;
+
+/// The size of a message header.
+const HEADER_LEN: usize = 16;
+
+/// A buffer size that fits the largest datagram the kernel sends in a dump.
+const BUF_SIZE: usize = 32768;
+
+/// The most messages queued for a stream of all messages that isn't being polled.
+///
+/// Further messages are dropped and reported with `ENOBUFS`, like the kernel reports dropped
+/// notifications.
+const MAX_QUEUED: usize = 4096;
+
+/// Rounds a length up to the alignment of netlink messages.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// A netlink message.
+///
+/// The header fields are decoded, and the payload holds everything after the header, such as a
+/// `struct ifinfomsg` followed by attributes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NetlinkMessage {
+    /// The message type, e.g. [`RTM_NEWLINK`] or [`NLMSG_DONE`].
+    pub kind: u16,
+
+    /// `NLM_F_*` flags.
+    pub flags: u16,
+
+    /// The sequence number of the request this message belongs to, or 0 for notifications.
+    pub seq: u32,
+
+    /// The port ID of the sender, or 0 for the kernel.
+    pub port: u32,
+
+    /// The message without its header.
+    pub payload: Vec<u8>,
+}
+
+impl NetlinkMessage {
+    /// Creates a message with sequence number and port ID 0.
+    pub fn new<P: Into<Vec<u8>>>(kind: u16, flags: u16, payload: P) -> NetlinkMessage {
+        NetlinkMessage {
+            kind,
+            flags,
+            seq: 0,
+            port: 0,
+            payload: payload.into(),
+        }
+    }
+
+    /// Encodes the message, including its header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = HEADER_LEN + self.payload.len();
+        let mut buf = Vec::with_capacity(align(len));
+        buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        buf.extend_from_slice(&self.kind.to_ne_bytes());
+        buf.extend_from_slice(&self.flags.to_ne_bytes());
+        buf.extend_from_slice(&self.seq.to_ne_bytes());
+        buf.extend_from_slice(&self.port.to_ne_bytes());
+        buf.extend_from_slice(&self.payload);
+        buf.resize(align(len), 0);
+        buf
+    }
+
+    /// Decodes all messages in a datagram.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a message is malformed.
+    pub fn parse(buf: &[u8]) -> io::Result<Vec<NetlinkMessage>> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let rest = &buf[offset..];
+            if rest.len() < HEADER_LEN {
+                return Err(invalid("truncated netlink message header"));
+            }
+            let len = u32::from_ne_bytes(rest[0..4].try_into().unwrap()) as usize;
+            if len < HEADER_LEN || len > rest.len() {
+                return Err(invalid("invalid netlink message length"));
+            }
+
+            messages.push(NetlinkMessage {
+                kind: u16::from_ne_bytes(rest[4..6].try_into().unwrap()),
+                flags: u16::from_ne_bytes(rest[6..8].try_into().unwrap()),
+                seq: u32::from_ne_bytes(rest[8..12].try_into().unwrap()),
+                port: u32::from_ne_bytes(rest[12..16].try_into().unwrap()),
+                payload: rest[HEADER_LEN..len].to_vec(),
+            });
+            offset += align(len);
+        }
+        Ok(messages)
+    }
+
+    /// Returns the error carried by an [`NLMSG_ERROR`] or [`NLMSG_DONE`] message.
+    ///
+    /// Acknowledgments, i.e. [`NLMSG_ERROR`] messages with error code 0, and all other messages
+    /// return `None`.
+    pub fn error(&self) -> Option<io::Error> {
+        match self.kind {
+            NLMSG_ERROR | NLMSG_DONE if self.payload.len() >= 4 => {
+                let code = i32::from_ne_bytes(self.payload[0..4].try_into().unwrap());
+                if code < 0 {
+                    Some(io::Error::from_raw_os_error(-code))
+                } else {
+                    None
+                }
+            }
+            NLMSG_ERROR => Some(invalid("truncated netlink error message")),
+            _ => None,
+        }
+    }
+}
+
+/// Creates an error of kind [`io::ErrorKind::InvalidData`].
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A netlink socket.
+///
+/// Datagrams are sent to the kernel and received from it. Use
+/// [`Async<NetlinkSocket>`][`crate::Async`] to send requests and wait for replies and
+/// notifications asynchronously.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::netlink::{NetlinkSocket, NETLINK_ROUTE, RTMGRP_LINK};
+///
+/// let socket = NetlinkSocket::new(NETLINK_ROUTE)?;
+/// socket.bind(RTMGRP_LINK)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct NetlinkSocket {
+    socket: Socket,
+    seq: AtomicU32,
+
+    /// Whether datagrams from other processes are received rather than dropped.
+    accept_processes: AtomicBool,
+
+    /// Received messages waiting for the streams they belong to.
+    dispatch: Mutex<Dispatch>,
+}
+
+impl NetlinkSocket {
+    /// Creates an unbound socket for a netlink protocol, e.g. [`NETLINK_ROUTE`].
+    pub fn new(protocol: i32) -> io::Result<NetlinkSocket> {
+        let socket = Socket::new(
+            Domain::from(libc::AF_NETLINK),
+            Type::raw(),
+            Some(Protocol::from(protocol)),
+        )?;
+        Ok(NetlinkSocket::from_socket(socket))
+    }
+
+    fn from_socket(socket: Socket) -> NetlinkSocket {
+        NetlinkSocket {
+            socket,
+            seq: AtomicU32::new(1),
+            accept_processes: AtomicBool::new(false),
+            dispatch: Mutex::new(Dispatch {
+                replies: HashMap::new(),
+                listeners: HashMap::new(),
+                next_listener: 0,
+                buf: Vec::new(),
+            }),
+        }
+    }
+
+    /// Binds the socket and joins the multicast groups in the `groups` bit mask, e.g.
+    /// [`RTMGRP_LINK`] `|` [`RTMGRP_IPV4_ROUTE`].
+    ///
+    /// The kernel assigns the port ID. Pass 0 to only send requests and receive replies.
+    pub fn bind(&self, groups: u32) -> io::Result<()> {
+        self.socket.bind(&netlink_addr(0, groups))
+    }
+
+    /// Joins a multicast group by number rather than by bit mask.
+    ///
+    /// Groups above 32 can only be joined this way.
+    pub fn add_membership(&self, group: u32) -> io::Result<()> {
+        sys::setsockopt(
+            self.as_raw_fd(),
+            libc::SOL_NETLINK,
+            libc::NETLINK_ADD_MEMBERSHIP,
+            group,
+        )
+    }
+
+    /// Leaves a multicast group by number.
+    pub fn drop_membership(&self, group: u32) -> io::Result<()> {
+        sys::setsockopt(
+            self.as_raw_fd(),
+            libc::SOL_NETLINK,
+            libc::NETLINK_DROP_MEMBERSHIP,
+            group,
+        )
+    }
+
+    /// Returns the port ID assigned to this socket.
+    ///
+    /// The port ID is 0 until the socket is bound or has sent its first message.
+    pub fn port_id(&self) -> io::Result<u32> {
+        let addr = self.socket.local_addr()?;
+        let addr = unsafe { &*(addr.as_ptr() as *const libc::sockaddr_nl) };
+        Ok(addr.nl_pid)
+    }
+
+    /// Returns a new sequence number for a request.
+    ///
+    /// Sequence numbers start at 1 and are never 0, which marks notifications.
+    pub fn next_seq(&self) -> u32 {
+        loop {
+            let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+            if seq != 0 {
+                return seq;
+            }
+        }
+    }
+
+    /// Sends a datagram to the kernel.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.send_to(buf, 0)
+    }
+
+    /// Sends a datagram to the socket with the given port ID, or to the kernel if it is 0.
+    pub fn send_to(&self, buf: &[u8], port: u32) -> io::Result<usize> {
+        self.socket.send_to(buf, &netlink_addr(port, 0))
+    }
+
+    /// Receives a datagram from the kernel.
+    ///
+    /// Datagrams sent by other processes are dropped unless
+    /// [`set_accept_processes()`][`NetlinkSocket::set_accept_processes()`] enabled them.
+    ///
+    /// A datagram that doesn't fit into the buffer is an error of kind
+    /// [`io::ErrorKind::InvalidData`], and its contents are lost. Buffers of 32 KiB fit every
+    /// datagram the kernel sends.
+    ///
+    /// If notifications arrive faster than they are received, the kernel drops them and this
+    /// returns an error with `ENOBUFS` once.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let (len, port) = self.recv_from(buf)?;
+            if port == 0 || self.accept_processes.load(Ordering::Relaxed) {
+                return Ok(len);
+            }
+        }
+    }
+
+    /// Receives a datagram and returns the port ID of its sender, which is 0 for the kernel.
+    ///
+    /// Unlike [`recv()`][`NetlinkSocket::recv()`], this returns datagrams from any sender. Fails
+    /// like [`recv()`][`NetlinkSocket::recv()`] on datagrams that don't fit.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, u32)> {
+        let (len, addr) = self.socket.recv_from_with_flags(buf, libc::MSG_TRUNC)?;
+        if len > buf.len() {
+            return Err(invalid("netlink datagram truncated"));
+        }
+        let addr = unsafe { &*(addr.as_ptr() as *const libc::sockaddr_nl) };
+        Ok((len, addr.nl_pid))
+    }
+
+    /// Sets whether datagrams sent by other processes are received.
+    ///
+    /// By default, only datagrams from the kernel are received, because any local process can
+    /// send datagrams to the port ID of this socket, e.g. forged notifications.
+    pub fn set_accept_processes(&self, accept: bool) {
+        self.accept_processes.store(accept, Ordering::Relaxed);
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.socket.take_error()
+    }
+
+    /// Moves the socket into or out of non-blocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.socket.set_nonblocking(nonblocking)
+    }
+
+    /// Starts receiving the replies to the request with sequence number `seq`.
+    pub(crate) fn subscribe_replies(&self, seq: u32) -> Subscription<'_> {
+        let mut dispatch = self.dispatch.lock().unwrap();
+        dispatch.replies.entry(seq).or_default();
+        Subscription {
+            socket: self,
+            key: Key::Reply(seq),
+        }
+    }
+
+    /// Starts receiving notifications and messages that don't reply to a request.
+    pub(crate) fn subscribe(&self) -> Subscription<'_> {
+        let mut dispatch = self.dispatch.lock().unwrap();
+        let id = dispatch.next_listener;
+        dispatch.next_listener += 1;
+        dispatch.listeners.insert(id, Listener::default());
+        Subscription {
+            socket: self,
+            key: Key::Listener(id),
+        }
+    }
+
+    /// Removes the next message queued for a subscription.
+    pub(crate) fn take(&self, key: Key) -> Option<io::Result<NetlinkMessage>> {
+        let mut dispatch = self.dispatch.lock().unwrap();
+        match key {
+            Key::Reply(seq) => dispatch.replies.get_mut(&seq)?.queue.pop_front().map(Ok),
+            Key::Listener(id) => {
+                let listener = dispatch.listeners.get_mut(&id)?;
+                match listener.queue.pop_front() {
+                    Some(msg) => Some(Ok(msg)),
+                    None if listener.overflowed => {
+                        listener.overflowed = false;
+                        Some(Err(io::Error::from_raw_os_error(libc::ENOBUFS)))
+                    }
+                    None => None,
+                }
+            }
+        }
+    }
+
+    /// Registers the current task to be woken when a message is queued for a subscription.
+    ///
+    /// Returns `true` if a message is queued already.
+    pub(crate) fn poll_queued(&self, key: Key, cx: &mut Context<'_>) -> bool {
+        let mut dispatch = self.dispatch.lock().unwrap();
+        match key {
+            Key::Reply(seq) => match dispatch.replies.get_mut(&seq) {
+                Some(reply) if reply.queue.is_empty() => {
+                    reply.waker = Some(cx.waker().clone());
+                    false
+                }
+                _ => true,
+            },
+            Key::Listener(id) => match dispatch.listeners.get_mut(&id) {
+                Some(listener) if listener.queue.is_empty() && !listener.overflowed => {
+                    listener.waker = Some(cx.waker().clone());
+                    false
+                }
+                _ => true,
+            },
+        }
+    }
+
+    /// Receives a datagram and queues its messages for the subscriptions they belong to.
+    ///
+    /// Returns a "would block" error if no datagram is waiting.
+    pub(crate) fn receive(&self) -> io::Result<()> {
+        let mut dispatch = self.dispatch.lock().unwrap();
+        let mut buf = Vec::new();
+        mem::swap(&mut buf, &mut dispatch.buf);
+        buf.resize(BUF_SIZE, 0);
+
+        let res = self.recv(&mut buf);
+        let res = match res {
+            Ok(len) => NetlinkMessage::parse(&buf[..len]).map(|messages| {
+                for msg in messages {
+                    dispatch.route(msg);
+                }
+            }),
+            // The kernel dropped notifications.
+            Err(err) if err.raw_os_error() == Some(libc::ENOBUFS) => {
+                for listener in dispatch.listeners.values_mut() {
+                    listener.overflow();
+                }
+                Ok(())
+            }
+            Err(err) => Err(err),
+        };
+        dispatch.buf = buf;
+        res
+    }
+}
+
+impl fmt::Debug for NetlinkSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetlinkSocket")
+            .field("socket", &self.socket)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+/// Identifies the stream a received message belongs to.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Key {
+    /// The replies to the request with this sequence number.
+    Reply(u32),
+
+    /// A stream of notifications and other messages.
+    Listener(usize),
+}
+
+/// Received messages waiting for the streams they belong to.
+struct Dispatch {
+    /// Replies by the sequence number of their request.
+    replies: HashMap<u32, Reply>,
+
+    /// Streams of notifications and messages that don't reply to a request.
+    listeners: HashMap<usize, Listener>,
+
+    /// The ID of the next listener.
+    next_listener: usize,
+
+    /// The receive buffer, reused between datagrams.
+    buf: Vec<u8>,
+}
+
+impl Dispatch {
+    /// Queues a message for the stream it belongs to, or drops it if there is none.
+    fn route(&mut self, msg: NetlinkMessage) {
+        if msg.seq != 0 {
+            if let Some(reply) = self.replies.get_mut(&msg.seq) {
+                let last = msg.kind == NLMSG_DONE
+                    || msg.kind == NLMSG_ERROR
+                    || msg.flags & NLM_F_MULTI == 0;
+                if reply.dropped {
+                    // Nobody is interested in the rest of this reply.
+                    if last {
+                        self.replies.remove(&msg.seq);
+                    }
+                    return;
+                }
+                reply.complete |= last;
+                reply.queue.push_back(msg);
+                if let Some(waker) = reply.waker.take() {
+                    waker.wake();
+                }
+                return;
+            }
+        }
+
+        // Every stream of all messages gets its own copy.
+        for listener in self.listeners.values_mut() {
+            if listener.queue.len() < MAX_QUEUED {
+                listener.queue.push_back(msg.clone());
+                if let Some(waker) = listener.waker.take() {
+                    waker.wake();
+                }
+            } else {
+                listener.overflow();
+            }
+        }
+    }
+}
+
+/// The queued reply to a request.
+#[derive(Default)]
+struct Reply {
+    queue: VecDeque<NetlinkMessage>,
+
+    /// The task waiting for the reply.
+    waker: Option<Waker>,
+
+    /// Whether the last message of the reply has been received.
+    complete: bool,
+
+    /// Whether the stream of the reply was dropped, so the rest of it is discarded.
+    dropped: bool,
+}
+
+/// The queue of a stream of notifications and other messages.
+#[derive(Default)]
+struct Listener {
+    queue: VecDeque<NetlinkMessage>,
+
+    /// The task waiting for a message.
+    waker: Option<Waker>,
+
+    /// Whether messages were dropped since the stream last reported it.
+    overflowed: bool,
+}
+
+impl Listener {
+    /// Records that messages were dropped and wakes the stream to report it.
+    fn overflow(&mut self) {
+        self.overflowed = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A stream's interest in received messages, dropped together with the stream.
+pub(crate) struct Subscription<'a> {
+    socket: &'a NetlinkSocket,
+    key: Key,
+}
+
+impl Subscription<'_> {
+    pub(crate) fn key(&self) -> Key {
+        self.key
+    }
+
+    /// Keeps queueing messages after the subscription is gone, for a stream created later.
+    pub(crate) fn keep(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        let mut dispatch = self.socket.dispatch.lock().unwrap();
+        match self.key {
+            Key::Reply(seq) => {
+                if let Some(reply) = dispatch.replies.get_mut(&seq) {
+                    if reply.complete {
+                        dispatch.replies.remove(&seq);
+                    } else {
+                        reply.queue.clear();
+                        reply.waker = None;
+                        reply.dropped = true;
+                    }
+                }
+            }
+            Key::Listener(id) => {
+                dispatch.listeners.remove(&id);
+            }
+        }
+    }
+}
+
+/// Creates a netlink socket address.
+fn netlink_addr(pid: u32, groups: u32) -> SockAddr {
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_pid = pid;
+    addr.nl_groups = groups;
+    unsafe {
+        SockAddr::from_raw_parts(
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl FromRawFd for NetlinkSocket {
+    unsafe fn from_raw_fd(fd: RawFd) -> NetlinkSocket {
+        NetlinkSocket::from_socket(Socket::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for NetlinkSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.socket.into_raw_fd()
+    }
+}
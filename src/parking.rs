@@ -24,6 +24,7 @@ use futures_lite::*;
 use once_cell::sync::Lazy;
 use vec_arena::Arena;
 
+use crate::ready::{Interest, Readiness};
 use crate::sys;
 
 static PARKER_COUNT: AtomicUsize = AtomicUsize::new(0);
@@ -404,9 +405,13 @@ impl Reactor {
             wakers: Mutex::new(Wakers {
                 tick_readable: 0,
                 tick_writable: 0,
+                readiness: Readiness::EMPTY,
                 readers: Vec::new(),
                 writers: Vec::new(),
+                errors: Vec::new(),
             }),
+            #[cfg(target_os = "linux")]
+            zerocopy: Mutex::new(Default::default()),
         });
         sources.insert(source.clone());
 
@@ -572,6 +577,7 @@ impl ReactorLock<'_> {
                     // Check if there is a source in the table with this key.
                     if let Some(source) = sources.get(ev.key) {
                         let mut w = source.wakers.lock().unwrap();
+                        w.readiness = w.readiness | ev.readiness;
 
                         // Wake readers if a readability event was emitted.
                         if ev.readable {
@@ -585,11 +591,22 @@ impl ReactorLock<'_> {
                             wakers.append(&mut w.writers);
                         }
 
+                        // Wake tasks waiting for errors if an error event was emitted.
+                        if ev.readiness.is_error() {
+                            // Consume completions of zero-copy sends, even cancelled ones that
+                            // nobody waits for. Otherwise the error condition would persist and
+                            // wake readers over and over.
+                            #[cfg(target_os = "linux")]
+                            source.recv_zerocopy();
+
+                            wakers.append(&mut w.errors);
+                        }
+
                         // Re-register if there are still writers or
                         // readers. The can happen if e.g. we were
                         // previously interested in both readability and
                         // writability, but only one of them was emitted.
-                        if !(w.writers.is_empty() && w.readers.is_empty()) {
+                        if !(w.writers.is_empty() && w.readers.is_empty() && w.errors.is_empty()) {
                             self.reactor.sys.interest(
                                 source.raw,
                                 source.key,
@@ -645,6 +662,10 @@ pub(crate) struct Source {
 
     /// Tasks interested in events on this source.
     wakers: Mutex<Wakers>,
+
+    /// Zero-copy sends on this source and their completions.
+    #[cfg(target_os = "linux")]
+    pub(crate) zerocopy: Mutex<crate::zerocopy::Completions>,
 }
 
 /// Tasks interested in events on a source.
@@ -656,16 +677,34 @@ struct Wakers {
     /// Last reactor tick that delivered a writability event.
     tick_writable: usize,
 
+    /// Readiness reported by events and not yet consumed by [`Source::ready()`].
+    readiness: Readiness,
+
     /// Tasks waiting for the next readability event.
     readers: Vec<Waker>,
 
     /// Tasks waiting for the next writability event.
     writers: Vec<Waker>,
+
+    /// Tasks waiting for the next error event.
+    errors: Vec<Waker>,
+}
+
+impl Wakers {
+    /// Clears and returns the bits of `bits` in the collected readiness.
+    fn consume(&mut self, bits: Readiness) -> Readiness {
+        let readiness = self.readiness;
+        self.readiness = readiness.without(bits);
+        readiness.intersect(bits)
+    }
 }
 
 impl Source {
     /// Waits until the I/O source is readable.
-    pub(crate) async fn readable(&self) -> io::Result<()> {
+    ///
+    /// Returns and clears the readiness for reading reported since the last wait that consumed
+    /// it.
+    pub(crate) async fn readable(&self) -> io::Result<Readiness> {
         let mut ticks = None;
 
         future::poll_fn(|cx| {
@@ -676,7 +715,7 @@ impl Source {
                 // If `tick_readable` has changed to a value other than the old reactor tick, that
                 // means a newer reactor tick has delivered a readability event.
                 if w.tick_readable != a && w.tick_readable != b {
-                    return Poll::Ready(Ok(()));
+                    return Poll::Ready(Ok(w.consume(Readiness::READ)));
                 }
             }
 
@@ -706,7 +745,10 @@ impl Source {
     }
 
     /// Waits until the I/O source is writable.
-    pub(crate) async fn writable(&self) -> io::Result<()> {
+    ///
+    /// Returns and clears the readiness for writing reported since the last wait that consumed
+    /// it.
+    pub(crate) async fn writable(&self) -> io::Result<Readiness> {
         let mut ticks = None;
 
         future::poll_fn(|cx| {
@@ -717,7 +759,7 @@ impl Source {
                 // If `tick_writable` has changed to a value other than the old reactor tick, that
                 // means a newer reactor tick has delivered a writability event.
                 if w.tick_writable != a && w.tick_writable != b {
-                    return Poll::Ready(Ok(()));
+                    return Poll::Ready(Ok(w.consume(Readiness::WRITE)));
                 }
             }
 
@@ -745,4 +787,128 @@ impl Source {
         })
         .await
     }
+
+    /// Waits until the I/O source is ready for any of the events in `interest`.
+    ///
+    /// Returns the readiness for the directions whose events arrived, as reported since the last
+    /// wait for them.
+    pub(crate) async fn ready(&self, interest: Interest) -> io::Result<Readiness> {
+        let readable = interest.is_readable();
+        let writable = interest.is_writable();
+        if !readable && !writable {
+            Ok(Readiness::EMPTY)
+        } else if readable && writable {
+            // Only consume the readiness of the directions whose events arrived.
+            let mut read = Box::pin(self.readable());
+            let mut write = Box::pin(self.writable());
+            future::poll_fn(|cx| {
+                let r = read.as_mut().poll(cx);
+                let w = write.as_mut().poll(cx);
+                match (r, w) {
+                    (Poll::Pending, Poll::Pending) => Poll::Pending,
+                    (Poll::Ready(res), Poll::Pending) | (Poll::Pending, Poll::Ready(res)) => {
+                        Poll::Ready(res)
+                    }
+                    (Poll::Ready(r), Poll::Ready(w)) => Poll::Ready(Ok(r? | w?)),
+                }
+            })
+            .await
+        } else if readable {
+            self.readable().await
+        } else {
+            self.writable().await
+        }
+    }
+
+    /// Wakes tasks waiting for readability and/or writability as if the reactor delivered an
+    /// event.
+    ///
+    /// This is used after the OS stops delivering events, e.g. when a socket is shut down.
+    pub(crate) fn wake(&self, readers: bool, writers: bool) {
+        let mut wakers = Vec::new();
+        {
+            let mut w = self.wakers.lock().unwrap();
+
+            // A fresh tick differs from the ticks remembered by every waiting task.
+            let tick = Reactor::get()
+                .ticker
+                .fetch_add(1, Ordering::SeqCst)
+                .wrapping_add(1);
+            w.readiness = w
+                .readiness
+                .with(Readiness::READ_CLOSED, readers)
+                .with(Readiness::WRITE_CLOSED, writers);
+            if readers {
+                w.tick_readable = tick;
+                wakers.append(&mut w.readers);
+            }
+            if writers {
+                w.tick_writable = tick;
+                wakers.append(&mut w.writers);
+            }
+        }
+
+        // Wake up tasks after the lock is released.
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Waits until the zero-copy sends numbered `first..=last` have completed.
+    ///
+    /// Completion notifications are queued on the socket error queue, which the OS reports as an
+    /// error condition even without interest in readability or writability.
+    #[cfg(target_os = "linux")]
+    pub(crate) async fn zerocopy_completed(&self, first: u32, last: u32) -> io::Result<()> {
+        future::poll_fn(|cx| {
+            let mut w = self.wakers.lock().unwrap();
+
+            // The reactor also records notifications with the wakers locked, so none can be
+            // missed between this check and registering the current task.
+            self.recv_zerocopy();
+            let mut completions = self.zerocopy.lock().unwrap();
+            if let Some(err) = completions.error.take() {
+                return Poll::Ready(Err(err));
+            }
+            if completions.covers(first, last) {
+                return Poll::Ready(Ok(()));
+            }
+            drop(completions);
+
+            // If there are no other tasks waiting for errors, re-register in the reactor.
+            if w.errors.is_empty() {
+                Reactor::get().sys.interest(
+                    self.raw,
+                    self.key,
+                    !w.readers.is_empty(),
+                    !w.writers.is_empty(),
+                )?;
+            }
+
+            // Register the current task's waker if not present already.
+            if w.errors.iter().all(|w| !w.will_wake(cx.waker())) {
+                w.errors.push(cx.waker().clone());
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Records completion notifications of zero-copy sends queued on the socket error queue.
+    ///
+    /// Does nothing if every zero-copy send has completed. An error is kept for the next wait for
+    /// completions. Must be called with the wakers locked.
+    #[cfg(target_os = "linux")]
+    fn recv_zerocopy(&self) {
+        let mut completions = self.zerocopy.lock().unwrap();
+        if completions.all_done() {
+            return;
+        }
+        match crate::zerocopy::recv_completions(self.raw, &mut completions) {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => completions.error = Some(err),
+            Ok(()) => {}
+        }
+    }
 }
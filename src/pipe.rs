@@ -0,0 +1,176 @@
+//! Anonymous pipes.
+
+use std::fs::File;
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+use crate::Async;
+
+/// Creates an anonymous pipe, returning its read and write ends.
+///
+/// Both ends are close-on-exec. Reading returns 0 bytes once the write end is dropped and all
+/// data has been read. Writing fails with [`io::ErrorKind::BrokenPipe`] once the read end is
+/// dropped, provided `SIGPIPE` is ignored as it is in Rust programs by default.
+///
+/// Note that [`close()`][`futures_lite::AsyncWriteExt::close()`] doesn't close the write end;
+/// drop it to signal end of stream to the reader.
+///
+/// # Examples
+///
+/// ```
+/// use futures_lite::*;
+///
+/// # blocking::block_on(async {
+/// let (mut reader, mut writer) = async_io::pipe()?;
+///
+/// writer.write_all(b"hello").await?;
+/// drop(writer);
+///
+/// let mut buf = String::new();
+/// reader.read_to_string(&mut buf).await?;
+/// assert_eq!(buf, "hello");
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn pipe() -> io::Result<(Async<PipeReader>, Async<PipeWriter>)> {
+    let mut fds = [0; 2];
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "illumos",
+    ))]
+    syscall!(pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC))?;
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "illumos",
+    )))]
+    syscall!(pipe(fds.as_mut_ptr()))?;
+
+    let (reader, writer) = unsafe {
+        (
+            PipeReader::from_raw_fd(fds[0]),
+            PipeWriter::from_raw_fd(fds[1]),
+        )
+    };
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "illumos",
+    )))]
+    for fd in &fds {
+        syscall!(fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC))?;
+    }
+
+    Ok((Async::new(reader)?, Async::new(writer)?))
+}
+
+/// The read end of an anonymous pipe.
+///
+/// Created by [`pipe()`].
+#[derive(Debug)]
+pub struct PipeReader(File);
+
+/// The write end of an anonymous pipe.
+///
+/// Created by [`pipe()`].
+#[derive(Debug)]
+pub struct PipeWriter(File);
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (&*self).read_vectored(bufs)
+    }
+}
+
+impl Read for &PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.0).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (&self.0).read_vectored(bufs)
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl Write for &PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.0).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&self.0).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for PipeReader {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for PipeReader {
+    unsafe fn from_raw_fd(fd: RawFd) -> PipeReader {
+        PipeReader(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for PipeReader {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl AsRawFd for PipeWriter {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for PipeWriter {
+    unsafe fn from_raw_fd(fd: RawFd) -> PipeWriter {
+        PipeWriter(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for PipeWriter {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
@@ -0,0 +1,462 @@
+//! Async child processes.
+//!
+//! This module wraps [`std::process::Command`] so that the standard input, output, and error of
+//! a child process are async pipes and its exit can be awaited without blocking a thread.
+//!
+//! On Linux, the exit of a child process is observed through a `pidfd` registered in the reactor.
+//! Elsewhere, or on kernels without `pidfd_open()`, a `SIGCHLD` handler is installed the first
+//! time it is needed, as described in the [`signal`][`crate::signal`] module.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_io::process::Command;
+//!
+//! # blocking::block_on(async {
+//! let output = Command::new("echo").arg("hello").output().await?;
+//! assert_eq!(output.stdout, b"hello\n");
+//! # std::io::Result::Ok(()) });
+//! ```
+
+use std::ffi::OsStr;
+use std::fmt;
+#[cfg(target_os = "linux")]
+use std::fs::File;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process;
+
+#[doc(no_inline)]
+pub use std::process::{ExitStatus, Output, Stdio};
+
+use futures_lite::*;
+
+use crate::pty::Slave;
+use crate::{signal, Async, PipeReader, PipeWriter};
+
+/// A builder for spawning child processes.
+///
+/// Like [`std::process::Command`], but spawns a [`Child`] whose pipes are async.
+pub struct Command {
+    inner: process::Command,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+}
+
+impl Command {
+    /// Creates a command for launching the program at path `program`.
+    ///
+    /// By default, the child process inherits the environment, the working directory, and the
+    /// standard input, output, and error of the current process.
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command::from(process::Command::new(program))
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// Sets an environment variable for the child process.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Command
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.env(key, val);
+        self
+    }
+
+    /// Removes an environment variable from the child process.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Command {
+        self.inner.env_remove(key);
+        self
+    }
+
+    /// Clears all environment variables of the child process.
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Sets the working directory of the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Command {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configures the standard input of the child process.
+    ///
+    /// With [`Stdio::piped()`], the spawned [`Child`] has an async [`stdin`][`Child::stdin`].
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdin(cfg);
+        self.stdin = true;
+        self
+    }
+
+    /// Configures the standard output of the child process.
+    ///
+    /// With [`Stdio::piped()`], the spawned [`Child`] has an async [`stdout`][`Child::stdout`].
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdout(cfg);
+        self.stdout = true;
+        self
+    }
+
+    /// Configures the standard error of the child process.
+    ///
+    /// With [`Stdio::piped()`], the spawned [`Child`] has an async [`stderr`][`Child::stderr`].
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stderr(cfg);
+        self.stderr = true;
+        self
+    }
+
+    /// Runs the child process in a pseudo-terminal.
+    ///
+    /// The standard input, output, and error of the child process are connected to `slave`, and
+    /// the child process starts a new session with `slave` as its controlling terminal. Read and
+    /// write the terminal through the master side returned by [`pty::open()`][`crate::pty::open()`].
+    pub fn pty(&mut self, slave: &Slave) -> io::Result<&mut Command> {
+        self.stdin(slave.stdio()?);
+        self.stdout(slave.stdio()?);
+        self.stderr(slave.stdio()?);
+
+        unsafe {
+            self.inner.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(self)
+    }
+
+    /// Spawns the command as a child process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::process::{Command, Stdio};
+    /// use futures_lite::*;
+    ///
+    /// # blocking::block_on(async {
+    /// let mut child = Command::new("cat")
+    ///     .stdin(Stdio::piped())
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    ///
+    /// let mut stdin = child.stdin.take().unwrap();
+    /// stdin.write_all(b"hello").await?;
+    /// drop(stdin);
+    ///
+    /// let mut buf = String::new();
+    /// child.stdout.as_mut().unwrap().read_to_string(&mut buf).await?;
+    /// assert_eq!(buf, "hello");
+    /// assert!(child.status().await?.success());
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        Child::new(self.inner.spawn()?)
+    }
+
+    /// Spawns the command and waits for it to exit.
+    ///
+    /// The standard input, output, and error are inherited unless configured otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::process::Command;
+    ///
+    /// # blocking::block_on(async {
+    /// let status = Command::new("true").status().await?;
+    /// assert!(status.success());
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn()?.status().await
+    }
+
+    /// Spawns the command, waits for it to exit, and collects its output.
+    ///
+    /// Unless configured otherwise, the standard input is null and the standard output and error
+    /// are captured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::process::Command;
+    ///
+    /// # blocking::block_on(async {
+    /// let output = Command::new("sh").arg("-c").arg("echo out; echo err >&2").output().await?;
+    /// assert_eq!(output.stdout, b"out\n");
+    /// assert_eq!(output.stderr, b"err\n");
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn output(&mut self) -> io::Result<Output> {
+        if !self.stdin {
+            self.inner.stdin(Stdio::null());
+        }
+        if !self.stdout {
+            self.inner.stdout(Stdio::piped());
+        }
+        if !self.stderr {
+            self.inner.stderr(Stdio::piped());
+        }
+        let child = self.inner.spawn();
+
+        // Restore the defaults of `spawn()` and `status()` for the next use of this command.
+        if !self.stdin {
+            self.inner.stdin(Stdio::inherit());
+        }
+        if !self.stdout {
+            self.inner.stdout(Stdio::inherit());
+        }
+        if !self.stderr {
+            self.inner.stderr(Stdio::inherit());
+        }
+        Child::new(child?)?.output().await
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Command {
+        Command {
+            inner,
+            stdin: false,
+            stdout: false,
+            stderr: false,
+        }
+    }
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// A spawned child process.
+///
+/// Dropping a `Child` doesn't kill or wait for the process.
+pub struct Child {
+    /// The standard input of the child process, if it was configured as piped.
+    pub stdin: Option<Async<PipeWriter>>,
+
+    /// The standard output of the child process, if it was configured as piped.
+    pub stdout: Option<Async<PipeReader>>,
+
+    /// The standard error of the child process, if it was configured as piped.
+    pub stderr: Option<Async<PipeReader>>,
+
+    /// The underlying child process.
+    inner: process::Child,
+
+    /// How the exit of the child process is observed.
+    exit: Exit,
+}
+
+/// A mechanism for observing the exit of a child process.
+enum Exit {
+    /// A `pidfd` that becomes readable when the process exits.
+    #[cfg(target_os = "linux")]
+    PidFd(Async<PidFd>),
+
+    /// The `SIGCHLD` handler.
+    Sigchld,
+}
+
+impl Child {
+    /// Wraps the pipes of a spawned child process and prepares for observing its exit.
+    fn new(mut inner: process::Child) -> io::Result<Child> {
+        let stdin = inner
+            .stdin
+            .take()
+            .map(|s| Async::new(unsafe { PipeWriter::from_raw_fd(s.into_raw_fd()) }))
+            .transpose()?;
+        let stdout = inner
+            .stdout
+            .take()
+            .map(|s| Async::new(unsafe { PipeReader::from_raw_fd(s.into_raw_fd()) }))
+            .transpose()?;
+        let stderr = inner
+            .stderr
+            .take()
+            .map(|s| Async::new(unsafe { PipeReader::from_raw_fd(s.into_raw_fd()) }))
+            .transpose()?;
+
+        #[cfg(target_os = "linux")]
+        let exit = match PidFd::open(inner.id()) {
+            Ok(pidfd) => Exit::PidFd(Async::new(pidfd)?),
+            Err(err) if PidFd::is_unsupported(&err) => Exit::Sigchld,
+            Err(err) => return Err(err),
+        };
+        #[cfg(not(target_os = "linux"))]
+        let exit = Exit::Sigchld;
+
+        if let Exit::Sigchld = exit {
+            signal::install(libc::SIGCHLD)?;
+        }
+
+        Ok(Child {
+            stdin,
+            stdout,
+            stderr,
+            inner,
+            exit,
+        })
+    }
+
+    /// Returns the OS-assigned process identifier.
+    pub fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    /// Forces the child process to exit by sending it `SIGKILL`.
+    ///
+    /// This is a no-op if the process has already exited.
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.inner.kill()
+    }
+
+    /// Returns the exit status if the child process has exited.
+    pub fn try_status(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.inner.try_wait()
+    }
+
+    /// Waits for the child process to exit and returns its exit status.
+    ///
+    /// The standard input of the child process is closed before waiting so that a process
+    /// reading until end of stream can exit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::process::Command;
+    ///
+    /// # blocking::block_on(async {
+    /// let mut child = Command::new("sleep").arg("0.1").spawn()?;
+    /// let status = child.status().await?;
+    /// assert!(status.success());
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+
+        loop {
+            // Snapshot the number of signals before checking so that an exit in between is not
+            // missed.
+            let seen = signal::count(libc::SIGCHLD);
+            if let Some(status) = self.inner.try_wait()? {
+                return Ok(status);
+            }
+
+            match &self.exit {
+                #[cfg(target_os = "linux")]
+                Exit::PidFd(pidfd) => pidfd.readable().await?,
+                Exit::Sigchld => signal::wait(libc::SIGCHLD, seen).await?,
+            }
+        }
+    }
+
+    /// Waits for the child process to exit and collects its output.
+    ///
+    /// Only the standard output and error configured as piped are collected. The standard input
+    /// is closed before waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::process::{Command, Stdio};
+    ///
+    /// # blocking::block_on(async {
+    /// let child = Command::new("echo").arg("hello").stdout(Stdio::piped()).spawn()?;
+    /// let output = child.output().await?;
+    /// assert_eq!(output.stdout, b"hello\n");
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn output(mut self) -> io::Result<Output> {
+        drop(self.stdin.take());
+
+        let stdout = read_to_end(self.stdout.take());
+        let stderr = read_to_end(self.stderr.take());
+        let ((stdout, stderr), status) =
+            future::try_join(future::try_join(stdout, stderr), self.status()).await?;
+
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl fmt::Debug for Child {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Child")
+            .field("stdin", &self.stdin)
+            .field("stdout", &self.stdout)
+            .field("stderr", &self.stderr)
+            .field("id", &self.inner.id())
+            .finish()
+    }
+}
+
+/// Reads a pipe until end of stream, or returns an empty buffer if there is no pipe.
+async fn read_to_end(pipe: Option<Async<PipeReader>>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buf).await?;
+    }
+    Ok(buf)
+}
+
+/// A file descriptor referring to a process.
+#[cfg(target_os = "linux")]
+struct PidFd(File);
+
+#[cfg(target_os = "linux")]
+impl PidFd {
+    /// Opens a `pidfd` for the process with the given ID.
+    fn open(pid: u32) -> io::Result<PidFd> {
+        let fd = syscall!(syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0))?;
+        Ok(PidFd(unsafe { File::from_raw_fd(fd as RawFd) }))
+    }
+
+    /// Returns `true` if an error means the kernel doesn't support `pidfd_open()`.
+    #[allow(clippy::match_like_matches_macro)]
+    fn is_unsupported(err: &io::Error) -> bool {
+        match err.raw_os_error() {
+            Some(libc::ENOSYS) | Some(libc::EPERM) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
@@ -0,0 +1,235 @@
+//! Pseudo-terminals.
+//!
+//! A pseudo-terminal is a pair of connected devices. A program running on the slave side sees an
+//! ordinary terminal, while everything it writes can be read from the master side and everything
+//! written into the master side appears as terminal input.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_io::process::Command;
+//! use futures_lite::*;
+//!
+//! # blocking::block_on(async {
+//! let (mut master, slave) = async_io::pty::open()?;
+//! master.get_ref().resize(24, 80)?;
+//!
+//! let mut cmd = Command::new("sh");
+//! cmd.arg("-c").arg("stty size").pty(&slave)?;
+//! let mut child = cmd.spawn()?;
+//!
+//! // Close our copies of the slave so that reading ends when the child exits.
+//! drop(cmd);
+//! drop(slave);
+//!
+//! let mut output = String::new();
+//! master.read_to_string(&mut output).await?;
+//! assert_eq!(output, "24 80\r\n");
+//! child.status().await?;
+//! # std::io::Result::Ok(()) });
+//! ```
+
+use std::ffi::{CStr, OsStr};
+use std::fs::{File, OpenOptions};
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::Async;
+
+/// Opens a pseudo-terminal, returning its master and slave sides.
+///
+/// Neither side becomes the controlling terminal of the current process.
+pub fn open() -> io::Result<(Async<Master>, Slave)> {
+    // Atomically set close-on-exec on platforms that support it.
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    let flags = libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC;
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    let flags = libc::O_RDWR | libc::O_NOCTTY;
+
+    let fd = syscall!(posix_openpt(flags))?;
+    let master = Master(unsafe { File::from_raw_fd(fd) });
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    syscall!(fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC))?;
+    syscall!(grantpt(fd))?;
+    syscall!(unlockpt(fd))?;
+
+    let path = slave_path(fd)?;
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_CLOEXEC)
+        .open(&path)?;
+
+    Ok((Async::new(master)?, Slave { file, path }))
+}
+
+/// Returns the path of the slave side of a pseudo-terminal.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn slave_path(master: RawFd) -> io::Result<PathBuf> {
+    let mut buf = [0 as libc::c_char; 256];
+    let res = unsafe { libc::ptsname_r(master, buf.as_mut_ptr(), buf.len()) };
+    if res != 0 {
+        return Err(io::Error::from_raw_os_error(res));
+    }
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    Ok(PathBuf::from(OsStr::from_bytes(name.to_bytes())))
+}
+
+/// Returns the path of the slave side of a pseudo-terminal.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn slave_path(master: RawFd) -> io::Result<PathBuf> {
+    use once_cell::sync::Lazy;
+    use std::sync::Mutex;
+
+    // `ptsname()` returns a static buffer.
+    static LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+    let _guard = LOCK.lock().unwrap();
+
+    let ptr = unsafe { libc::ptsname(master) };
+    if ptr.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    let name = unsafe { CStr::from_ptr(ptr) };
+    Ok(PathBuf::from(OsStr::from_bytes(name.to_bytes())))
+}
+
+/// The master side of a pseudo-terminal.
+///
+/// Reading returns what programs on the slave side write, and writing sends them input. Once
+/// every handle to the slave side is closed, reading returns end of stream.
+#[derive(Debug)]
+pub struct Master(File);
+
+impl Master {
+    /// Sets the window size of the terminal.
+    ///
+    /// Programs on the slave side receive `SIGWINCH`.
+    pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+        size.ws_row = rows;
+        size.ws_col = cols;
+        syscall!(ioctl(self.0.as_raw_fd(), libc::TIOCSWINSZ as _, &size))?;
+        Ok(())
+    }
+
+    /// Returns the window size of the terminal as `(rows, cols)`.
+    pub fn window_size(&self) -> io::Result<(u16, u16)> {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+        syscall!(ioctl(self.0.as_raw_fd(), libc::TIOCGWINSZ as _, &mut size))?;
+        Ok((size.ws_row, size.ws_col))
+    }
+}
+
+/// Treats the error returned after the slave side is closed as end of stream.
+fn eof_on_hangup(res: io::Result<usize>) -> io::Result<usize> {
+    match res {
+        Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+        res => res,
+    }
+}
+
+impl Read for Master {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (&*self).read_vectored(bufs)
+    }
+}
+
+impl Read for &Master {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        eof_on_hangup((&self.0).read(buf))
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        eof_on_hangup((&self.0).read_vectored(bufs))
+    }
+}
+
+impl Write for Master {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl Write for &Master {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.0).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&self.0).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for Master {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for Master {
+    unsafe fn from_raw_fd(fd: RawFd) -> Master {
+        Master(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for Master {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+/// The slave side of a pseudo-terminal.
+///
+/// Pass it to [`Command::pty()`][`crate::process::Command::pty()`] to run a program in the
+/// terminal.
+#[derive(Debug)]
+pub struct Slave {
+    file: File,
+    path: PathBuf,
+}
+
+impl Slave {
+    /// Returns the path of the terminal device, e.g. `/dev/pts/3`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a new handle to the terminal for use as standard input, output, or error.
+    pub fn stdio(&self) -> io::Result<Stdio> {
+        Ok(Stdio::from(self.file.try_clone()?))
+    }
+}
+
+impl AsRawFd for Slave {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for Slave {
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
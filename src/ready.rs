@@ -0,0 +1,172 @@
+//! Interest in I/O events and the readiness reported by the OS.
+
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+/// Events an I/O handle is waiting for.
+///
+/// Interests can be combined with the `|` operator.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::Interest;
+///
+/// let interest = Interest::READABLE | Interest::WRITABLE;
+/// assert!(interest.is_readable());
+/// assert!(interest.is_writable());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interest(u8);
+
+impl Interest {
+    /// No interest in any event.
+    ///
+    /// Waiting for it returns immediately with no readiness.
+    pub const EMPTY: Interest = Interest(0);
+
+    /// Interest in readability.
+    pub const READABLE: Interest = Interest(0b01);
+
+    /// Interest in writability.
+    pub const WRITABLE: Interest = Interest(0b10);
+
+    /// Returns `true` if this includes interest in readability.
+    pub fn is_readable(self) -> bool {
+        self.0 & Interest::READABLE.0 != 0
+    }
+
+    /// Returns `true` if this includes interest in writability.
+    pub fn is_writable(self) -> bool {
+        self.0 & Interest::WRITABLE.0 != 0
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Interest {
+    fn bitor_assign(&mut self, other: Interest) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interest")
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .finish()
+    }
+}
+
+/// Readiness of an I/O handle as reported by the OS.
+///
+/// Besides readability and writability, this tells apart conditions that all wake readers or
+/// writers: the peer closing its half of a connection, a pending error, and priority data such
+/// as TCP urgent data.
+///
+/// Priority data is only reported on Linux, Android, illumos, and Windows.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Readiness(u8);
+
+impl Readiness {
+    pub(crate) const EMPTY: Readiness = Readiness(0);
+    pub(crate) const READABLE: Readiness = Readiness(0b00_0001);
+    pub(crate) const WRITABLE: Readiness = Readiness(0b00_0010);
+    pub(crate) const READ_CLOSED: Readiness = Readiness(0b00_0100);
+    pub(crate) const WRITE_CLOSED: Readiness = Readiness(0b00_1000);
+    pub(crate) const ERROR: Readiness = Readiness(0b01_0000);
+    pub(crate) const PRIORITY: Readiness = Readiness(0b10_0000);
+
+    /// Readiness consumed by a wait for readability.
+    pub(crate) const READ: Readiness = Readiness(
+        Readiness::READABLE.0
+            | Readiness::READ_CLOSED.0
+            | Readiness::PRIORITY.0
+            | Readiness::ERROR.0,
+    );
+
+    /// Readiness consumed by a wait for writability.
+    pub(crate) const WRITE: Readiness =
+        Readiness(Readiness::WRITABLE.0 | Readiness::WRITE_CLOSED.0 | Readiness::ERROR.0);
+
+    /// Returns `true` if data can be read.
+    pub fn is_readable(self) -> bool {
+        self.contains(Readiness::READABLE)
+    }
+
+    /// Returns `true` if data can be written.
+    pub fn is_writable(self) -> bool {
+        self.contains(Readiness::WRITABLE)
+    }
+
+    /// Returns `true` if the read half is closed, e.g. because the peer shut down writing.
+    pub fn is_read_closed(self) -> bool {
+        self.contains(Readiness::READ_CLOSED)
+    }
+
+    /// Returns `true` if the write half is closed, e.g. because the connection was hung up.
+    pub fn is_write_closed(self) -> bool {
+        self.contains(Readiness::WRITE_CLOSED)
+    }
+
+    /// Returns `true` if an error condition is pending.
+    pub fn is_error(self) -> bool {
+        self.contains(Readiness::ERROR)
+    }
+
+    /// Returns `true` if priority data can be read.
+    pub fn is_priority(self) -> bool {
+        self.contains(Readiness::PRIORITY)
+    }
+
+    /// Returns this readiness with the bits of `other` set if `cond` is `true`.
+    pub(crate) fn with(self, other: Readiness, cond: bool) -> Readiness {
+        if cond {
+            self | other
+        } else {
+            self
+        }
+    }
+
+    /// Returns this readiness with the bits of `other` cleared.
+    pub(crate) fn without(self, other: Readiness) -> Readiness {
+        Readiness(self.0 & !other.0)
+    }
+
+    /// Returns only the bits of this readiness that are also set in `other`.
+    pub(crate) fn intersect(self, other: Readiness) -> Readiness {
+        Readiness(self.0 & other.0)
+    }
+
+    fn contains(self, other: Readiness) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Readiness {
+    type Output = Readiness;
+
+    fn bitor(self, other: Readiness) -> Readiness {
+        Readiness(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for Readiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Readiness")
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("read_closed", &self.is_read_closed())
+            .field("write_closed", &self.is_write_closed())
+            .field("error", &self.is_error())
+            .field("priority", &self.is_priority())
+            .finish()
+    }
+}
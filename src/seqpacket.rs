@@ -0,0 +1,164 @@
+//! Unix sockets of type `SOCK_SEQPACKET`.
+
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::Path;
+
+use socket2::{Domain, SockAddr, Socket, Type};
+
+/// Flags for sending without raising `SIGPIPE` where the OS supports it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const SEND_FLAGS: libc::c_int = 0;
+
+/// A Unix listener accepting connections of type `SOCK_SEQPACKET`.
+///
+/// Like [`UnixListener`][`std::os::unix::net::UnixListener`], but accepted connections preserve
+/// message boundaries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io::UnixSeqpacketListener;
+///
+/// let listener = UnixSeqpacketListener::bind("/tmp/socket")?;
+/// let conn = listener.accept()?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct UnixSeqpacketListener(Socket);
+
+impl UnixSeqpacketListener {
+    /// Creates a listener bound to the specified path.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixSeqpacketListener> {
+        let socket = Socket::new(Domain::unix(), Type::seqpacket(), None)?;
+        socket.bind(&SockAddr::unix(path)?)?;
+        socket.listen(128)?;
+        Ok(UnixSeqpacketListener(socket))
+    }
+
+    /// Accepts a new incoming connection.
+    pub fn accept(&self) -> io::Result<UnixSeqpacket> {
+        let (socket, _) = self.0.accept()?;
+        Ok(UnixSeqpacket(socket))
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Moves the listener into or out of non-blocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+/// A connected Unix socket of type `SOCK_SEQPACKET`.
+///
+/// Every [`send()`][`UnixSeqpacket::send()`] transmits one message, and every
+/// [`recv()`][`UnixSeqpacket::recv()`] receives exactly one message. If the buffer passed to
+/// `recv()` is too small, the rest of the message is discarded.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::UnixSeqpacket;
+///
+/// let (conn1, conn2) = UnixSeqpacket::pair()?;
+/// conn1.send(b"hello")?;
+///
+/// let mut buf = [0u8; 1024];
+/// let len = conn2.recv(&mut buf)?;
+/// assert_eq!(&buf[..len], b"hello");
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct UnixSeqpacket(Socket);
+
+impl UnixSeqpacket {
+    /// Creates a connection to the specified path.
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<UnixSeqpacket> {
+        let socket = Socket::new(Domain::unix(), Type::seqpacket(), None)?;
+        socket.connect(&SockAddr::unix(path)?)?;
+        Ok(UnixSeqpacket(socket))
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(UnixSeqpacket, UnixSeqpacket)> {
+        let (socket1, socket2) = Socket::pair(Domain::unix(), Type::seqpacket(), None)?;
+        Ok((UnixSeqpacket(socket1), UnixSeqpacket(socket2)))
+    }
+
+    /// Sends a message to the connected peer.
+    ///
+    /// Returns the number of bytes written.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send_with_flags(buf, SEND_FLAGS)
+    }
+
+    /// Receives a message from the connected peer.
+    ///
+    /// Returns the number of bytes read.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Moves the socket into or out of non-blocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Wraps a socket that is being connected.
+    pub(crate) fn from_socket(socket: Socket) -> UnixSeqpacket {
+        UnixSeqpacket(socket)
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for UnixSeqpacketListener {
+    unsafe fn from_raw_fd(fd: RawFd) -> UnixSeqpacketListener {
+        UnixSeqpacketListener(Socket::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for UnixSeqpacketListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl AsRawFd for UnixSeqpacket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for UnixSeqpacket {
+    unsafe fn from_raw_fd(fd: RawFd) -> UnixSeqpacket {
+        UnixSeqpacket(Socket::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for UnixSeqpacket {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
@@ -0,0 +1,333 @@
+//! Unix signals delivered as async streams.
+//!
+//! The first time a signal is listened for, a handler is installed for it and stays installed for
+//! the rest of the process. From then on, the default action of the signal, such as terminating
+//! the process on `SIGINT`, no longer happens. A handler that was installed before is still
+//! called after ours.
+//!
+//! The handler only counts the signal and writes a byte into a pipe registered in the reactor,
+//! which is async-signal-safe. Listeners compare the counts with the ones they have seen, so
+//! every listener observes every signal it is interested in. Whichever listener finds the pipe
+//! readable drains it and wakes all the others.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use async_io::signal::{Signals, SIGINT, SIGTERM};
+//! use futures_lite::*;
+//!
+//! # blocking::block_on(async {
+//! let mut signals = Signals::new(&[SIGINT, SIGTERM])?;
+//!
+//! while let Some(signal) = signals.next().await {
+//!     println!("received signal {}", signal);
+//!     break;
+//! }
+//! # std::io::Result::Ok(()) });
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::io::IntoRawFd;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+use futures_lite::*;
+use once_cell::sync::{Lazy, OnceCell};
+
+use crate::{Async, PipeReader};
+
+#[doc(no_inline)]
+pub use libc::{
+    SIGALRM, SIGCHLD, SIGCONT, SIGHUP, SIGINT, SIGPIPE, SIGQUIT, SIGTERM, SIGTSTP, SIGUSR1,
+    SIGUSR2, SIGWINCH,
+};
+
+/// One more than the largest signal number on any supported platform.
+const NSIG: usize = 128;
+
+/// Bookkeeping for a signal number.
+struct Slot {
+    /// The number of times the signal was received.
+    count: AtomicUsize,
+
+    /// Whether our handler is installed.
+    installed: AtomicBool,
+
+    /// The handler that was installed before ours.
+    previous: AtomicUsize,
+
+    /// Whether the previous handler takes `siginfo_t` arguments.
+    previous_siginfo: AtomicBool,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const SLOT: Slot = Slot {
+    count: AtomicUsize::new(0),
+    installed: AtomicBool::new(false),
+    previous: AtomicUsize::new(libc::SIG_DFL),
+    previous_siginfo: AtomicBool::new(false),
+};
+
+static SLOTS: [Slot; NSIG] = [SLOT; NSIG];
+
+/// Serializes installation of handlers.
+static INSTALL: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The write end of the pipe, written into by the handler.
+static PIPE_WRITER: AtomicI32 = AtomicI32::new(-1);
+
+/// The read end of the pipe, registered in the reactor.
+static PIPE_READER: OnceCell<Async<PipeReader>> = OnceCell::new();
+
+/// Tasks waiting for signals, woken every time the pipe is drained.
+static WAITERS: Lazy<Mutex<Vec<Waker>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A stream of Unix signals.
+///
+/// Yields the number of every signal received that this listener is interested in. Any number of
+/// listeners can be created for the same signal, and each of them receives it. A signal received
+/// several times before the listener is polled again is yielded once, just like the OS coalesces
+/// pending signals.
+///
+/// See the [module-level documentation](self) for how signals are handled.
+pub struct Signals {
+    /// The signals of interest and their counts as last seen by this listener.
+    signals: Vec<(i32, usize)>,
+
+    /// Waits until the pipe is readable.
+    readable: Option<Pin<Box<dyn Future<Output = io::Result<()>> + Send>>>,
+}
+
+impl Signals {
+    /// Starts listening for the given signals.
+    ///
+    /// Signals received before this call are not yielded.
+    ///
+    /// Returns an error if a signal number is invalid or if it is `SIGKILL`, `SIGSTOP`, `SIGILL`,
+    /// `SIGFPE`, `SIGSEGV`, or `SIGBUS`, which cannot be handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io::signal::{Signals, SIGHUP};
+    ///
+    /// let signals = Signals::new(&[SIGHUP])?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn new(signals: &[i32]) -> io::Result<Signals> {
+        let mut list = Vec::with_capacity(signals.len());
+        for &signal in signals {
+            let forbidden = [
+                libc::SIGKILL,
+                libc::SIGSTOP,
+                libc::SIGILL,
+                libc::SIGFPE,
+                libc::SIGSEGV,
+                libc::SIGBUS,
+            ];
+            if forbidden.contains(&signal) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("signal {} cannot be handled", signal),
+                ));
+            }
+            install(signal)?;
+            list.push((signal, count(signal)));
+        }
+        Ok(Signals {
+            signals: list,
+            readable: None,
+        })
+    }
+}
+
+impl Stream for Signals {
+    type Item = i32;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<i32>> {
+        let reader = PIPE_READER.get().expect("signal pipe is not initialized");
+
+        loop {
+            // Register interest before checking the counts so that a signal received in between
+            // wakes this task.
+            register(cx);
+            let readable = self
+                .readable
+                .get_or_insert_with(|| Box::pin(reader.readable()));
+            match readable.as_mut().poll(cx) {
+                Poll::Ready(res) => {
+                    self.readable = None;
+                    if res.is_err() {
+                        // The pipe can no longer be registered in the reactor.
+                        return Poll::Ready(None);
+                    }
+                    dispatch(reader);
+                }
+                Poll::Pending => {
+                    for (signal, seen) in &mut self.signals {
+                        let count = count(*signal);
+                        if *seen != count {
+                            *seen = count;
+                            return Poll::Ready(Some(*signal));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Signals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let signals: Vec<i32> = self.signals.iter().map(|(signal, _)| *signal).collect();
+        f.debug_struct("Signals")
+            .field("signals", &signals)
+            .finish()
+    }
+}
+
+/// Returns the number of times a signal was received since its handler was installed.
+pub(crate) fn count(signal: i32) -> usize {
+    SLOTS[signal as usize].count.load(Ordering::SeqCst)
+}
+
+/// Installs the handler for a signal unless it is already installed.
+pub(crate) fn install(signal: i32) -> io::Result<()> {
+    if signal <= 0 || signal as usize >= NSIG {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid signal number {}", signal),
+        ));
+    }
+    let slot = &SLOTS[signal as usize];
+
+    PIPE_READER.get_or_try_init(|| {
+        let (reader, writer) = crate::pipe()?;
+
+        // The write end stays non-blocking so that the handler never blocks.
+        PIPE_WRITER.store(writer.into_inner()?.into_raw_fd(), Ordering::SeqCst);
+        io::Result::Ok(reader)
+    })?;
+
+    let _guard = INSTALL.lock().unwrap();
+    if slot.installed.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    // Remember the previous handler before ours can run.
+    let mut previous: libc::sigaction = unsafe { mem::zeroed() };
+    syscall!(sigaction(signal, ptr::null(), &mut previous))?;
+    slot.previous.store(previous.sa_sigaction, Ordering::SeqCst);
+    slot.previous_siginfo
+        .store(previous.sa_flags & libc::SA_SIGINFO != 0, Ordering::SeqCst);
+
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = handler as *const () as libc::sighandler_t;
+    action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART | libc::SA_NOCLDSTOP;
+    syscall!(sigemptyset(&mut action.sa_mask))?;
+    syscall!(sigaction(signal, &action, ptr::null_mut()))?;
+
+    slot.installed.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Waits until a signal's count differs from `seen`.
+///
+/// The handler for the signal must be installed.
+pub(crate) async fn wait(signal: i32, seen: usize) -> io::Result<()> {
+    let reader = PIPE_READER.get().expect("signal pipe is not initialized");
+
+    loop {
+        if count(signal) != seen {
+            return Ok(());
+        }
+
+        // Register interest before checking the count again so that a signal received in
+        // between wakes this task.
+        let readable = reader.readable();
+        pin!(readable);
+        future::poll_fn(|cx| {
+            register(cx);
+            if readable.as_mut().poll(cx)?.is_ready() || count(signal) != seen {
+                return Poll::Ready(io::Result::Ok(()));
+            }
+            Poll::Pending
+        })
+        .await?;
+
+        dispatch(reader);
+    }
+}
+
+/// Registers the current task to be woken the next time the pipe is drained.
+fn register(cx: &mut Context<'_>) {
+    let mut waiters = WAITERS.lock().unwrap();
+    if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+        waiters.push(cx.waker().clone());
+    }
+}
+
+/// Reads everything from the pipe and wakes all tasks waiting for signals.
+///
+/// This is the only place that reads from the pipe. A listener that consumes the readiness of the
+/// pipe may take the byte another listener was waiting for, so it wakes all of them.
+fn dispatch(reader: &Async<PipeReader>) {
+    let mut pipe = reader.get_ref();
+    let mut buf = [0u8; 64];
+    while let Ok(n) = pipe.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+    }
+
+    let wakers: Vec<Waker> = WAITERS.lock().unwrap().drain(..).collect();
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+/// Handles a signal.
+extern "C" fn handler(signal: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+    unsafe {
+        let errno = *errno_location();
+        let slot = &SLOTS[signal as usize];
+
+        slot.count.fetch_add(1, Ordering::SeqCst);
+        let fd = PIPE_WRITER.load(Ordering::SeqCst);
+        libc::write(fd, b"1".as_ptr() as *const libc::c_void, 1);
+
+        let previous = slot.previous.load(Ordering::SeqCst);
+        if previous != libc::SIG_DFL && previous != libc::SIG_IGN {
+            if slot.previous_siginfo.load(Ordering::SeqCst) {
+                let f: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                    mem::transmute(previous);
+                f(signal, info, ctx);
+            } else {
+                let f: extern "C" fn(libc::c_int) = mem::transmute(previous);
+                f(signal);
+            }
+        }
+
+        *errno_location() = errno;
+    }
+}
+
+/// Returns a pointer to `errno` of the current thread.
+unsafe fn errno_location() -> *mut libc::c_int {
+    #[cfg(any(target_os = "linux", target_os = "dragonfly"))]
+    let ptr = libc::__errno_location();
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    let ptr = libc::__errno();
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    let ptr = libc::__error();
+    #[cfg(target_os = "illumos")]
+    let ptr = libc::___errno();
+    ptr
+}
@@ -0,0 +1,308 @@
+//! Async standard input, output, and error.
+//!
+//! Pipes, terminals, and sockets are registered in the reactor. Regular files and other file
+//! descriptors the reactor doesn't support are read and written on the thread pool used by
+//! [`Async::new_blocking_fallback()`] instead.
+//!
+//! Registering a file descriptor in the reactor puts it into non-blocking mode. Because the mode
+//! belongs to the open file rather than the descriptor, it is visible to other processes sharing
+//! it, e.g. a shell sharing a terminal, and to the other standard streams if they refer to the
+//! same open file. The flag is cleared again when the last handle to a standard stream
+//! registered in the reactor is dropped, and only on streams where this crate set it.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem::ManuallyDrop;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+
+use futures_lite::*;
+use once_cell::sync::Lazy;
+
+use crate::fallback::Unblock;
+use crate::{sys, Async};
+
+/// Handles to the standard streams, indexed by file descriptor.
+///
+/// A file descriptor can only be registered once, and operations on the thread pool must run one
+/// at a time, so all handles to a stream share one.
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| {
+    Mutex::new(Registry {
+        reactor: [Weak::new(), Weak::new(), Weak::new()],
+        pool: [Weak::new(), Weak::new(), Weak::new()],
+        nonblocking: [false; 3],
+        live: 0,
+    })
+});
+
+struct Registry {
+    /// Streams registered in the reactor.
+    reactor: [Weak<Registered>; 3],
+
+    /// Streams served by the thread pool.
+    pool: [Weak<Mutex<Unblock<StdFd>>>; 3],
+
+    /// Whether this crate put the stream into non-blocking mode.
+    nonblocking: [bool; 3],
+
+    /// The number of streams registered in the reactor.
+    ///
+    /// Standard streams may share an open file, e.g. a terminal, so non-blocking mode is only
+    /// cleared once none of them is registered.
+    live: usize,
+}
+
+/// Returns a handle to the standard input of the current process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_lite::*;
+///
+/// # blocking::block_on(async {
+/// let mut line = String::new();
+/// async_io::stdin()?.read_to_string(&mut line).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn stdin() -> io::Result<Stdin> {
+    Ok(Stdin(Handle::new(libc::STDIN_FILENO)?))
+}
+
+/// Returns a handle to the standard output of the current process.
+///
+/// Writes are not buffered, except that on the thread pool a write returns before it completes.
+/// Call [`flush()`][`futures_lite::AsyncWriteExt::flush()`] to wait for it to complete.
+///
+/// # Examples
+///
+/// ```
+/// use futures_lite::*;
+///
+/// # blocking::block_on(async {
+/// let mut stdout = async_io::stdout()?;
+/// stdout.write_all(b"hello\n").await?;
+/// stdout.flush().await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn stdout() -> io::Result<Stdout> {
+    Ok(Stdout(Handle::new(libc::STDOUT_FILENO)?))
+}
+
+/// Returns a handle to the standard error of the current process.
+///
+/// Writes are not buffered, except that on the thread pool a write returns before it completes.
+/// Call [`flush()`][`futures_lite::AsyncWriteExt::flush()`] to wait for it to complete.
+///
+/// # Examples
+///
+/// ```
+/// use futures_lite::*;
+///
+/// # blocking::block_on(async {
+/// let mut stderr = async_io::stderr()?;
+/// stderr.write_all(b"warning\n").await?;
+/// stderr.flush().await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn stderr() -> io::Result<Stderr> {
+    Ok(Stderr(Handle::new(libc::STDERR_FILENO)?))
+}
+
+/// An async handle to the standard input of the current process.
+///
+/// Created by [`stdin()`].
+#[derive(Debug)]
+pub struct Stdin(Handle);
+
+/// An async handle to the standard output of the current process.
+///
+/// Created by [`stdout()`].
+#[derive(Debug)]
+pub struct Stdout(Handle);
+
+/// An async handle to the standard error of the current process.
+///
+/// Created by [`stderr()`].
+#[derive(Debug)]
+pub struct Stderr(Handle);
+
+impl AsyncRead for Stdin {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.0 {
+            Handle::Reactor(io) => Pin::new(&mut &*io.0).poll_read(cx, buf),
+            Handle::Pool(unblock) => unblock.lock().unwrap().poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stdout {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_flush(cx)
+    }
+}
+
+impl AsyncWrite for Stderr {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.0.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.poll_flush(cx)
+    }
+}
+
+/// A standard stream, either registered in the reactor or served by the thread pool.
+enum Handle {
+    Reactor(Arc<Registered>),
+    Pool(Arc<Mutex<Unblock<StdFd>>>),
+}
+
+impl Handle {
+    /// Creates a handle to a standard stream.
+    fn new(fd: RawFd) -> io::Result<Handle> {
+        let mut registry = REGISTRY.lock().unwrap();
+        let index = fd as usize;
+        if let Some(io) = registry.reactor[index].upgrade() {
+            return Ok(Handle::Reactor(io));
+        }
+        if let Some(unblock) = registry.pool[index].upgrade() {
+            return Ok(Handle::Pool(unblock));
+        }
+
+        let flags = syscall!(fcntl(fd, libc::F_GETFL))?;
+        match Async::new(StdFd(fd)) {
+            Ok(io) => {
+                if flags & libc::O_NONBLOCK == 0 {
+                    registry.nonblocking[index] = true;
+                }
+                registry.live += 1;
+
+                let io = Arc::new(Registered(ManuallyDrop::new(io)));
+                registry.reactor[index] = Arc::downgrade(&io);
+                Ok(Handle::Reactor(io))
+            }
+            // The reactor doesn't support this file descriptor, e.g. a regular file.
+            Err(err) if sys::is_unsupported(&err) => {
+                let unblock = Arc::new(Mutex::new(Unblock::new(StdFd(fd))));
+                registry.pool[index] = Arc::downgrade(&unblock);
+                Ok(Handle::Pool(unblock))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self {
+            Handle::Reactor(io) => Pin::new(&mut &*io.0).poll_write(cx, buf),
+            Handle::Pool(unblock) => unblock.lock().unwrap().poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self {
+            Handle::Reactor(_) => Poll::Ready(Ok(())),
+            Handle::Pool(unblock) => unblock.lock().unwrap().poll_flush(cx),
+        }
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Handle::Reactor(io) => f.debug_tuple("Reactor").field(&io.0.get_ref().0).finish(),
+            Handle::Pool(unblock) => {
+                let fd = unblock.lock().unwrap().get_ref().0;
+                f.debug_tuple("Pool").field(&fd).finish()
+            }
+        }
+    }
+}
+
+/// A standard stream registered in the reactor.
+///
+/// Clears non-blocking mode when the last registered stream is dropped.
+struct Registered(ManuallyDrop<Async<StdFd>>);
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        let mut registry = REGISTRY.lock().unwrap();
+
+        // Deregister before clearing non-blocking mode so that the reactor never sees a blocking
+        // file descriptor. The lock keeps other threads from registering it in the meantime.
+        unsafe { ManuallyDrop::drop(&mut self.0) };
+
+        registry.live -= 1;
+        if registry.live > 0 {
+            return;
+        }
+
+        for fd in 0..3 {
+            if registry.nonblocking[fd] {
+                registry.nonblocking[fd] = false;
+                let fd = fd as RawFd;
+                let _ = syscall!(fcntl(fd, libc::F_GETFL)).and_then(|flags| {
+                    syscall!(fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK))
+                });
+            }
+        }
+    }
+}
+
+/// A standard stream, which is never closed.
+struct StdFd(RawFd);
+
+impl StdFd {
+    /// Borrows the file descriptor as a [`File`] without taking ownership of it.
+    fn file(&self) -> ManuallyDrop<File> {
+        ManuallyDrop::new(unsafe { File::from_raw_fd(self.0) })
+    }
+}
+
+impl AsRawFd for StdFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl Read for &StdFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file().read(buf)
+    }
+}
+
+impl Write for &StdFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
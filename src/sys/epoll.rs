@@ -6,6 +6,7 @@ use std::os::unix::io::RawFd;
 use std::ptr;
 use std::time::Duration;
 
+use crate::ready::Readiness;
 use crate::sys::Event;
 
 pub struct Reactor {
@@ -59,13 +60,26 @@ impl Reactor {
     }
 
     pub fn insert(&self, fd: RawFd, key: usize) -> io::Result<()> {
-        let flags = syscall!(fcntl(fd, libc::F_GETFL))?;
-        syscall!(fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK))?;
+        // Register first so that the file status flags stay untouched if epoll refuses the file
+        // descriptor.
         let mut ev = libc::epoll_event {
             events: 0,
             u64: key as u64,
         };
-        syscall!(epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut ev))?;
+        syscall!(epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut ev)).map_err(|err| {
+            if err.raw_os_error() == Some(libc::EPERM) {
+                crate::sys::unsupported()
+            } else {
+                err
+            }
+        })?;
+
+        let res = syscall!(fcntl(fd, libc::F_GETFL))
+            .and_then(|flags| syscall!(fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK)));
+        if let Err(err) = res {
+            let _ = self.remove(fd);
+            return Err(err);
+        }
         Ok(())
     }
 
@@ -109,7 +123,7 @@ impl Reactor {
 
         let res = syscall!(epoll_wait(
             self.epoll_fd,
-            events.list.as_mut_ptr() as *mut libc::epoll_event,
+            events.list.as_mut_ptr(),
             events.list.len() as libc::c_int,
             timeout_ms as libc::c_int,
         ))?;
@@ -167,10 +181,23 @@ impl Events {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
-        self.list[..self.len].iter().map(|ev| Event {
-            readable: (ev.events as libc::c_int & read_flags()) != 0,
-            writable: (ev.events as libc::c_int & write_flags()) != 0,
-            key: ev.u64 as usize,
+        self.list[..self.len].iter().map(|ev| {
+            let flags = ev.events as libc::c_int;
+            Event {
+                readable: (flags & read_flags()) != 0,
+                writable: (flags & write_flags()) != 0,
+                readiness: Readiness::EMPTY
+                    .with(Readiness::READABLE, (flags & libc::EPOLLIN) != 0)
+                    .with(Readiness::WRITABLE, (flags & libc::EPOLLOUT) != 0)
+                    .with(
+                        Readiness::READ_CLOSED,
+                        (flags & (libc::EPOLLRDHUP | libc::EPOLLHUP)) != 0,
+                    )
+                    .with(Readiness::WRITE_CLOSED, (flags & libc::EPOLLHUP) != 0)
+                    .with(Readiness::ERROR, (flags & libc::EPOLLERR) != 0)
+                    .with(Readiness::PRIORITY, (flags & libc::EPOLLPRI) != 0),
+                key: ev.u64 as usize,
+            }
         })
     }
 }
@@ -6,6 +6,7 @@ use std::os::unix::net::UnixStream;
 use std::ptr;
 use std::time::Duration;
 
+use crate::ready::Readiness;
 use crate::sys::Event;
 
 pub struct Reactor {
@@ -192,11 +193,26 @@ impl Events {
         // event is reported as EVFILT_READ with the EV_EOF flag.
         //
         // https://github.com/golang/go/commit/23aad448b1e3f7c3b4ba2af90120bde91ac865b4
-        self.list[..self.len].iter().map(|ev| Event {
-            readable: ev.filter == libc::EVFILT_READ,
-            writable: ev.filter == libc::EVFILT_WRITE
-                || (ev.filter == libc::EVFILT_READ && (ev.flags & libc::EV_EOF) != 0),
-            key: ev.udata as usize,
+        self.list[..self.len].iter().map(|ev| {
+            let eof = (ev.flags & libc::EV_EOF) != 0;
+            Event {
+                readable: ev.filter == libc::EVFILT_READ,
+                writable: ev.filter == libc::EVFILT_WRITE
+                    || (ev.filter == libc::EVFILT_READ && eof),
+                readiness: Readiness::EMPTY
+                    .with(Readiness::READABLE, ev.filter == libc::EVFILT_READ)
+                    .with(Readiness::WRITABLE, ev.filter == libc::EVFILT_WRITE)
+                    .with(
+                        Readiness::READ_CLOSED,
+                        ev.filter == libc::EVFILT_READ && eof,
+                    )
+                    .with(
+                        Readiness::WRITE_CLOSED,
+                        ev.filter == libc::EVFILT_WRITE && eof,
+                    )
+                    .with(Readiness::ERROR, eof && ev.fflags != 0),
+                key: ev.udata as usize,
+            }
         })
     }
 }
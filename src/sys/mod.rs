@@ -7,18 +7,9 @@ use std::os::unix::io::{FromRawFd, RawFd};
 use std::os::windows::io::{FromRawSocket, RawSocket};
 
 use cfg_if::cfg_if;
+use socket2::Socket;
 
-#[cfg(unix)]
-macro_rules! syscall {
-    ($fn:ident $args:tt) => {{
-        let res = unsafe { libc::$fn $args };
-        if res == -1 {
-            Err(std::io::Error::last_os_error())
-        } else {
-            Ok(res)
-        }
-    }};
-}
+use crate::ready::Readiness;
 
 cfg_if! {
     if #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))] {
@@ -45,9 +36,52 @@ cfg_if! {
 pub struct Event {
     pub readable: bool,
     pub writable: bool,
+    pub readiness: Readiness,
     pub key: usize,
 }
 
+/// Creates the error for file descriptors the reactor doesn't support.
+///
+/// Only epoll refuses file descriptors, kqueue accepts everything.
+#[cfg(unix)]
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "android", target_os = "illumos")),
+    allow(dead_code)
+)]
+pub fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, Unsupported)
+}
+
+/// Returns `true` if an error was created by [`unsupported()`].
+#[cfg(unix)]
+pub fn is_unsupported(err: &io::Error) -> bool {
+    match err.get_ref() {
+        Some(inner) => inner.is::<Unsupported>(),
+        None => false,
+    }
+}
+
+/// The cause of the error for file descriptors the reactor doesn't support.
+#[cfg(unix)]
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "android", target_os = "illumos")),
+    allow(dead_code)
+)]
+#[derive(Debug)]
+struct Unsupported;
+
+#[cfg(unix)]
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            "file descriptor not supported by the reactor, use `Async::new_blocking_fallback()`",
+        )
+    }
+}
+
+#[cfg(unix)]
+impl std::error::Error for Unsupported {}
+
 /// Shuts down the write side of a socket.
 ///
 /// If this source is not a socket, the `shutdown()` syscall error is ignored.
@@ -68,3 +102,45 @@ pub fn shutdown_write(#[cfg(unix)] raw: RawFd, #[cfg(windows)] raw: RawSocket) -
         _ => Ok(()),
     }
 }
+
+/// Borrows a raw socket as a [`Socket`] without taking ownership of it.
+pub fn socket_ref(#[cfg(unix)] raw: RawFd, #[cfg(windows)] raw: RawSocket) -> ManuallyDrop<Socket> {
+    #[cfg(unix)]
+    let socket = unsafe { Socket::from_raw_fd(raw) };
+    #[cfg(windows)]
+    let socket = unsafe { Socket::from_raw_socket(raw) };
+    ManuallyDrop::new(socket)
+}
+
+/// Sets the value of a socket option.
+#[cfg(unix)]
+pub fn setsockopt<T>(
+    raw: RawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: T,
+) -> io::Result<()> {
+    syscall!(setsockopt(
+        raw,
+        level,
+        name,
+        &value as *const T as *const libc::c_void,
+        std::mem::size_of::<T>() as libc::socklen_t,
+    ))?;
+    Ok(())
+}
+
+/// Gets the value of a socket option.
+#[cfg(unix)]
+pub fn getsockopt<T: Copy>(raw: RawFd, level: libc::c_int, name: libc::c_int) -> io::Result<T> {
+    let mut value = std::mem::MaybeUninit::<T>::zeroed();
+    let mut len = std::mem::size_of::<T>() as libc::socklen_t;
+    syscall!(getsockopt(
+        raw,
+        level,
+        name,
+        value.as_mut_ptr() as *mut libc::c_void,
+        &mut len,
+    ))?;
+    Ok(unsafe { value.assume_init() })
+}
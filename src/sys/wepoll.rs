@@ -9,6 +9,7 @@ use std::time::Duration;
 use wepoll_sys_stjepang as we;
 use winapi::um::winsock2;
 
+use crate::ready::Readiness;
 use crate::sys::Event;
 
 macro_rules! wepoll {
@@ -179,6 +180,16 @@ impl Events {
         self.list[..self.len].iter().map(|ev| Event {
             readable: (ev.events & READ_FLAGS) != 0,
             writable: (ev.events & WRITE_FLAGS) != 0,
+            readiness: Readiness::EMPTY
+                .with(Readiness::READABLE, (ev.events & we::EPOLLIN) != 0)
+                .with(Readiness::WRITABLE, (ev.events & we::EPOLLOUT) != 0)
+                .with(
+                    Readiness::READ_CLOSED,
+                    (ev.events & (we::EPOLLRDHUP | we::EPOLLHUP)) != 0,
+                )
+                .with(Readiness::WRITE_CLOSED, (ev.events & we::EPOLLHUP) != 0)
+                .with(Readiness::ERROR, (ev.events & we::EPOLLERR) != 0)
+                .with(Readiness::PRIORITY, (ev.events & we::EPOLLPRI) != 0),
             key: unsafe { ev.data.u64 } as usize,
         })
     }
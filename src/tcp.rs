@@ -0,0 +1,275 @@
+//! Configuration of TCP keepalive probes and accepted connections.
+
+use std::io;
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use socket2::Socket;
+
+#[cfg(unix)]
+use crate::sys;
+use crate::Async;
+
+/// Parameters of TCP keepalive probes (`SO_KEEPALIVE`).
+///
+/// Keepalive probes detect dead peers on idle connections and keep NAT mappings alive. Parameters
+/// left unset use the system defaults.
+///
+/// The interval and the number of retries can only be configured on Linux, Android, FreeBSD,
+/// DragonFly BSD, macOS, and iOS. They are ignored on other platforms.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::TcpKeepalive;
+/// use std::time::Duration;
+///
+/// let keepalive = TcpKeepalive::new()
+///     .with_time(Duration::from_secs(60))
+///     .with_interval(Duration::from_secs(10))
+///     .with_retries(5);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TcpKeepalive {
+    time: Option<Duration>,
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl TcpKeepalive {
+    /// Creates keepalive parameters that use the system defaults.
+    pub fn new() -> TcpKeepalive {
+        TcpKeepalive::default()
+    }
+
+    /// Sets how long a connection must be idle before the first probe is sent.
+    pub fn with_time(self, time: Duration) -> TcpKeepalive {
+        TcpKeepalive {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    /// Sets the time between two successive probes.
+    pub fn with_interval(self, interval: Duration) -> TcpKeepalive {
+        TcpKeepalive {
+            interval: Some(interval),
+            ..self
+        }
+    }
+
+    /// Sets how many unanswered probes are sent before the connection is dropped.
+    pub fn with_retries(self, retries: u32) -> TcpKeepalive {
+        TcpKeepalive {
+            retries: Some(retries),
+            ..self
+        }
+    }
+
+    /// Returns how long a connection must be idle before the first probe is sent.
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// Returns the time between two successive probes.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Returns how many unanswered probes are sent before the connection is dropped.
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+}
+
+/// Socket options applied to every connection accepted by
+/// [`Async::<TcpListener>::accept_with()`][`crate::Async::<std::net::TcpListener>::accept_with()`].
+///
+/// Not all platforms let accepted streams inherit socket options from the listener, so these
+/// options are set on each accepted stream instead. Options left unset keep the system defaults.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::{TcpKeepalive, TcpOptions};
+/// use std::time::Duration;
+///
+/// let options = TcpOptions::new()
+///     .with_keepalive(TcpKeepalive::new().with_time(Duration::from_secs(60)))
+///     .with_nodelay(true);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TcpOptions {
+    keepalive: Option<TcpKeepalive>,
+    linger: Option<Duration>,
+    nodelay: Option<bool>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    user_timeout: Option<Duration>,
+}
+
+impl TcpOptions {
+    /// Creates options that leave every socket option at its system default.
+    pub fn new() -> TcpOptions {
+        TcpOptions::default()
+    }
+
+    /// Enables keepalive probes with the given parameters.
+    pub fn with_keepalive(self, keepalive: TcpKeepalive) -> TcpOptions {
+        TcpOptions {
+            keepalive: Some(keepalive),
+            ..self
+        }
+    }
+
+    /// Sets how long closing a stream waits for unsent data to be transmitted (`SO_LINGER`).
+    pub fn with_linger(self, linger: Duration) -> TcpOptions {
+        TcpOptions {
+            linger: Some(linger),
+            ..self
+        }
+    }
+
+    /// Enables or disables the Nagle algorithm (`TCP_NODELAY`).
+    pub fn with_nodelay(self, nodelay: bool) -> TcpOptions {
+        TcpOptions {
+            nodelay: Some(nodelay),
+            ..self
+        }
+    }
+
+    /// Sets how long transmitted data may stay unacknowledged before the connection is dropped
+    /// (`TCP_USER_TIMEOUT`).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn with_user_timeout(self, timeout: Duration) -> TcpOptions {
+        TcpOptions {
+            user_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Returns the keepalive parameters, if set.
+    pub fn keepalive(&self) -> Option<TcpKeepalive> {
+        self.keepalive
+    }
+
+    /// Returns the `SO_LINGER` duration, if set.
+    pub fn linger(&self) -> Option<Duration> {
+        self.linger
+    }
+
+    /// Returns whether `TCP_NODELAY` is enabled, if set.
+    pub fn nodelay(&self) -> Option<bool> {
+        self.nodelay
+    }
+
+    /// Returns the `TCP_USER_TIMEOUT`, if set.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn user_timeout(&self) -> Option<Duration> {
+        self.user_timeout
+    }
+
+    /// Sets the options on a stream.
+    pub(crate) fn apply(&self, stream: &Async<TcpStream>) -> io::Result<()> {
+        if let Some(keepalive) = self.keepalive {
+            stream.set_keepalive(Some(keepalive))?;
+        }
+        if let Some(linger) = self.linger {
+            stream.set_linger(Some(linger))?;
+        }
+        if let Some(nodelay) = self.nodelay {
+            stream.set_nodelay(nodelay)?;
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            if let Some(timeout) = self.user_timeout {
+                stream.set_user_timeout(Some(timeout))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Enables keepalive probes with the given parameters, or disables them.
+pub(crate) fn set_keepalive(socket: &Socket, keepalive: Option<&TcpKeepalive>) -> io::Result<()> {
+    let keepalive = match keepalive {
+        None => return socket.set_keepalive(None),
+        Some(keepalive) => keepalive,
+    };
+
+    match keepalive.time {
+        Some(time) => socket.set_keepalive(Some(time))?,
+        None => enable(socket)?,
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "macos",
+        target_os = "ios",
+    ))]
+    {
+        let fd = socket.as_raw_fd();
+        if let Some(interval) = keepalive.interval {
+            #[allow(clippy::legacy_numeric_constants)]
+            let secs = interval
+                .as_secs()
+                .max(1)
+                .min(libc::c_int::max_value() as u64) as libc::c_int;
+            sys::setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPINTVL, secs)?;
+        }
+        if let Some(retries) = keepalive.retries {
+            #[allow(clippy::legacy_numeric_constants)]
+            let retries = retries.min(libc::c_int::max_value() as u32) as libc::c_int;
+            sys::setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPCNT, retries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the keepalive parameters, or `None` if keepalive probes are disabled.
+pub(crate) fn keepalive(socket: &Socket) -> io::Result<Option<TcpKeepalive>> {
+    let time = match socket.keepalive()? {
+        None => return Ok(None),
+        Some(time) => time,
+    };
+
+    #[allow(unused_mut)]
+    let mut keepalive = TcpKeepalive::new().with_time(time);
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "macos",
+        target_os = "ios",
+    ))]
+    {
+        let fd = socket.as_raw_fd();
+        let interval: libc::c_int = sys::getsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPINTVL)?;
+        let retries: libc::c_int = sys::getsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPCNT)?;
+        keepalive = keepalive
+            .with_interval(Duration::from_secs(interval as u64))
+            .with_retries(retries as u32);
+    }
+    Ok(Some(keepalive))
+}
+
+/// Enables `SO_KEEPALIVE` without changing the idle time.
+fn enable(socket: &Socket) -> io::Result<()> {
+    #[cfg(unix)]
+    let res = sys::setsockopt(
+        socket.as_raw_fd(),
+        libc::SOL_SOCKET,
+        libc::SO_KEEPALIVE,
+        1 as libc::c_int,
+    );
+    // Windows only enables keepalive together with an idle time, so use its default of 2 hours.
+    #[cfg(windows)]
+    let res = socket.set_keepalive(Some(Duration::from_secs(2 * 60 * 60)));
+    res
+}
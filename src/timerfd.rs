@@ -0,0 +1,133 @@
+//! Kernel timers with `timerfd`.
+
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::ptr;
+use std::time::Duration;
+
+/// The clock a [`TimerFd`] measures time with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Clock {
+    /// A clock that never jumps and stops while the system is suspended.
+    Monotonic,
+
+    /// The wall clock, which jumps when the system time is changed.
+    Realtime,
+
+    /// Like [`Clock::Monotonic`], but keeps running while the system is suspended.
+    Boottime,
+}
+
+impl Clock {
+    fn id(self) -> libc::clockid_t {
+        match self {
+            Clock::Monotonic => libc::CLOCK_MONOTONIC,
+            Clock::Realtime => libc::CLOCK_REALTIME,
+            Clock::Boottime => libc::CLOCK_BOOTTIME,
+        }
+    }
+}
+
+/// A timer kept by the kernel.
+///
+/// Unlike [`Timer`][`crate::Timer`], a `TimerFd` counts its expirations: if the timer fires
+/// several times before it is read, the read returns how many times it fired.
+///
+/// Use [`Async<TimerFd>`][`crate::Async`] to wait for expirations asynchronously.
+///
+/// # Examples
+///
+/// ```
+/// use async_io::timerfd::{Clock, TimerFd};
+/// use std::time::Duration;
+///
+/// let timer = TimerFd::new(Clock::Monotonic)?;
+/// timer.set(Duration::from_millis(10), Some(Duration::from_millis(10)))?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+pub struct TimerFd(File);
+
+impl TimerFd {
+    /// Creates a disarmed timer that measures time with `clock`.
+    pub fn new(clock: Clock) -> io::Result<TimerFd> {
+        let fd = syscall!(timerfd_create(clock.id(), libc::TFD_CLOEXEC))?;
+        Ok(TimerFd(unsafe { File::from_raw_fd(fd) }))
+    }
+
+    /// Arms the timer to fire after `initial` and then every `interval`, if set.
+    ///
+    /// A zero `initial` duration fires the timer right away. Arming the timer again replaces the
+    /// previous settings and resets the expiration count.
+    pub fn set(&self, initial: Duration, interval: Option<Duration>) -> io::Result<()> {
+        // A zero value would disarm the timer instead.
+        let initial = initial.max(Duration::from_nanos(1));
+        let spec = libc::itimerspec {
+            it_interval: timespec(interval.unwrap_or_default()),
+            it_value: timespec(initial),
+        };
+        syscall!(timerfd_settime(
+            self.0.as_raw_fd(),
+            0,
+            &spec,
+            ptr::null_mut()
+        ))?;
+        Ok(())
+    }
+
+    /// Disarms the timer.
+    pub fn disarm(&self) -> io::Result<()> {
+        let spec: libc::itimerspec = unsafe { mem::zeroed() };
+        syscall!(timerfd_settime(
+            self.0.as_raw_fd(),
+            0,
+            &spec,
+            ptr::null_mut()
+        ))?;
+        Ok(())
+    }
+
+    /// Returns the number of expirations since the timer was armed or last read.
+    ///
+    /// Blocks until the timer has fired at least once.
+    pub fn read(&self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        syscall!(read(
+            self.0.as_raw_fd(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len()
+        ))?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+}
+
+impl AsRawFd for TimerFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for TimerFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> TimerFd {
+        TimerFd(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for TimerFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+/// Converts a duration into a `timespec`.
+fn timespec(dur: Duration) -> libc::timespec {
+    // Some targets have padding fields, so start from zeroes.
+    let mut ts: libc::timespec = unsafe { mem::zeroed() };
+    #[allow(clippy::legacy_numeric_constants)]
+    let secs = dur.as_secs().min(libc::time_t::max_value() as u64);
+    ts.tv_sec = secs as libc::time_t;
+    ts.tv_nsec = dur.subsec_nanos() as _;
+    ts
+}
@@ -0,0 +1,236 @@
+//! Zero-copy sends with `MSG_ZEROCOPY` and their completion notifications.
+//!
+//! Every successful `send()` with `MSG_ZEROCOPY` is assigned the next number of a per-socket
+//! counter. When the kernel no longer needs the pages of a send, it queues a notification on the
+//! socket error queue that covers a range of those numbers.
+
+use std::fmt;
+use std::io::{self, IoSliceMut};
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::ptr;
+
+use crate::parking::Source;
+use crate::{cmsg, sys};
+
+/// Origin of completion notifications for zero-copy sends.
+const SO_EE_ORIGIN_ZEROCOPY: u8 = 5;
+
+/// Enables `SO_ZEROCOPY` on a socket.
+pub(crate) fn enable(fd: RawFd) -> io::Result<()> {
+    sys::setsockopt(fd, libc::SOL_SOCKET, libc::SO_ZEROCOPY, 1 as libc::c_int)
+}
+
+/// Returns `true` if an error means zero-copy sends are not supported.
+#[allow(clippy::match_like_matches_macro)]
+pub(crate) fn is_unsupported(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(libc::ENOPROTOOPT) | Some(libc::EOPNOTSUPP) | Some(libc::EINVAL) => true,
+        _ => false,
+    }
+}
+
+/// Sequence numbers of zero-copy sends on a socket and which of them have completed.
+///
+/// Numbers wrap around, so they are compared relative to `done`.
+#[derive(Default)]
+pub(crate) struct Completions {
+    /// The number the kernel assigns to the next zero-copy send.
+    next: u32,
+
+    /// Every send numbered below this one has completed.
+    done: u32,
+
+    /// Disjoint, sorted inclusive ranges of completed sends above `done`.
+    ranges: Vec<(u32, u32)>,
+
+    /// Buffers of cancelled sends with the numbers of their first and last sends.
+    ///
+    /// The kernel may still transmit from these, so they are dropped only once their sends have
+    /// completed.
+    held: Vec<(u32, u32, Box<dyn Send>)>,
+
+    /// An error from reading the socket error queue, returned by the next wait for completions.
+    pub(crate) error: Option<io::Error>,
+}
+
+impl fmt::Debug for Completions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Completions")
+            .field("next", &self.next)
+            .field("done", &self.done)
+            .field("ranges", &self.ranges)
+            .field("held", &self.held.len())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl Completions {
+    /// Records a successful zero-copy send and returns its number.
+    pub(crate) fn submit(&mut self) -> u32 {
+        let seq = self.next;
+        self.next = self.next.wrapping_add(1);
+        seq
+    }
+
+    /// Returns `true` if every send has completed.
+    pub(crate) fn all_done(&self) -> bool {
+        self.done == self.next
+    }
+
+    /// Returns `true` if every send numbered `first..=last` has completed.
+    pub(crate) fn covers(&self, first: u32, last: u32) -> bool {
+        covered(self.done, &self.ranges, first, last)
+    }
+
+    /// Keeps the buffer of the sends numbered `first..=last` until they have completed.
+    pub(crate) fn hold(&mut self, first: u32, last: u32, buf: Box<dyn Send>) {
+        if !self.covers(first, last) {
+            self.held.push((first, last, buf));
+        }
+    }
+
+    /// Records that the sends numbered `first..=last` have completed.
+    fn complete(&mut self, first: u32, last: u32) {
+        if before(last, self.done) {
+            return;
+        }
+        let first = if before(first, self.done) {
+            self.done
+        } else {
+            first
+        };
+        self.ranges.push((first, last));
+
+        // Keep the ranges sorted and merge the ones that overlap or touch.
+        let done = self.done;
+        self.ranges.sort_by_key(|&(lo, _)| lo.wrapping_sub(done));
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(self.ranges.len());
+        for &(lo, hi) in &self.ranges {
+            match merged.last_mut() {
+                Some(prev) if !before(prev.1.wrapping_add(1), lo) => {
+                    if before(prev.1, hi) {
+                        prev.1 = hi;
+                    }
+                }
+                _ => merged.push((lo, hi)),
+            }
+        }
+
+        // Advance `done` past the range that starts at it.
+        if let Some(&(lo, hi)) = merged.first() {
+            if lo == self.done {
+                self.done = hi.wrapping_add(1);
+                merged.remove(0);
+            }
+        }
+        self.ranges = merged;
+
+        // Drop the buffers of cancelled sends the kernel is done with.
+        let (done, ranges) = (self.done, &self.ranges);
+        self.held
+            .retain(|&(first, last, _)| !covered(done, ranges, first, last));
+    }
+}
+
+/// Returns `true` if every send numbered `first..=last` is below `done` or within `ranges`.
+fn covered(done: u32, ranges: &[(u32, u32)], first: u32, last: u32) -> bool {
+    if before(last, done) {
+        return true;
+    }
+    let first = if before(first, done) { done } else { first };
+    ranges
+        .iter()
+        .any(|&(lo, hi)| !before(first, lo) && !before(hi, last))
+}
+
+/// Returns `true` if sequence number `a` comes before `b`.
+fn before(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+/// Sends data without copying it, or copying it if `zero_copy` is `false`.
+pub(crate) fn send(fd: RawFd, buf: &[u8], zero_copy: bool) -> io::Result<usize> {
+    let mut flags = libc::MSG_NOSIGNAL;
+    if zero_copy {
+        flags |= libc::MSG_ZEROCOPY;
+    }
+    let n = syscall!(send(
+        fd,
+        buf.as_ptr() as *const libc::c_void,
+        buf.len(),
+        flags
+    ))?;
+    Ok(n as usize)
+}
+
+/// Reads completion notifications from the socket error queue and records them.
+///
+/// Other entries on the error queue are discarded. Reading stops once every zero-copy send has
+/// completed, so entries queued after that are left for the application. Returns a "would block"
+/// error if nothing is queued.
+pub(crate) fn recv_completions(fd: RawFd, completions: &mut Completions) -> io::Result<()> {
+    let mut received_any = false;
+    loop {
+        let mut control = [0u64; 16];
+        let res = cmsg::recvmsg(
+            fd,
+            &mut [IoSliceMut::new(&mut [])],
+            (
+                control.as_mut_ptr() as *mut libc::c_void,
+                mem::size_of_val(&control),
+            ),
+            libc::MSG_ERRQUEUE,
+        );
+        let received = match res {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock && received_any => return Ok(()),
+            res => res?,
+        };
+        received_any = true;
+
+        let control = unsafe {
+            std::slice::from_raw_parts(control.as_ptr() as *const u8, received.control_len)
+        };
+        let mut offset = 0;
+        while let Some((level, ty, data)) = cmsg::next_cmsg(control, &mut offset) {
+            let is_recverr = (level, ty) == (libc::SOL_IP, libc::IP_RECVERR)
+                || (level, ty) == (libc::SOL_IPV6, libc::IPV6_RECVERR);
+            if !is_recverr || data.len() < mem::size_of::<libc::sock_extended_err>() {
+                continue;
+            }
+
+            let err =
+                unsafe { ptr::read_unaligned(data.as_ptr() as *const libc::sock_extended_err) };
+            if err.ee_errno == 0 && err.ee_origin == SO_EE_ORIGIN_ZEROCOPY {
+                // The notification covers the inclusive range `ee_info..=ee_data`.
+                completions.complete(err.ee_info, err.ee_data);
+            }
+        }
+        if completions.all_done() {
+            return Ok(());
+        }
+    }
+}
+
+/// The buffer of a zero-copy send in progress.
+///
+/// If the send is cancelled before the kernel is done with the buffer, the socket keeps it.
+pub(crate) struct Pending<'a, B: Send + 'static> {
+    pub(crate) source: &'a Source,
+
+    /// The buffer, until it is returned to the caller.
+    pub(crate) buf: Option<Box<B>>,
+
+    /// The numbers of the first and last zero-copy sends made from the buffer.
+    pub(crate) seqs: Option<(u32, u32)>,
+}
+
+impl<B: Send + 'static> Drop for Pending<'_, B> {
+    fn drop(&mut self) {
+        if let (Some(buf), Some((first, last))) = (self.buf.take(), self.seqs) {
+            let mut completions = self.source.zerocopy.lock().unwrap();
+            completions.hold(first, last, buf);
+        }
+    }
+}
@@ -7,7 +7,7 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use async_io::{Async, Timer};
+use async_io::{Async, Interest, Readiness, Timer};
 use blocking::block_on;
 use futures_lite::*;
 #[cfg(unix)]
@@ -353,3 +353,1368 @@ fn close() -> io::Result<()> {
         Ok(())
     })
 }
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_send_recv_msg() -> io::Result<()> {
+    use async_io::{ControlMessage, PacketInfo, RecvAncillary, SendAncillary};
+    use std::io::{IoSlice, IoSliceMut};
+    use std::net::Ipv4Addr;
+
+    block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([0, 0, 0, 0], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket1.set_recv_packet_info(true)?;
+        socket1.set_recv_ttl(true)?;
+        socket1.set_recv_timestamps(true)?;
+        let addr1 = (Ipv4Addr::LOCALHOST, socket1.get_ref().local_addr()?.port()).into();
+
+        let (head, tail) = LOREM_IPSUM.split_at(10);
+        let mut ancillary = SendAncillary::new();
+        ancillary.set_ttl(42);
+        socket2
+            .send_msg(
+                &[IoSlice::new(head), IoSlice::new(tail)],
+                &ancillary,
+                Some(addr1),
+            )
+            .await?;
+
+        let mut buf1 = [0u8; 10];
+        let mut buf2 = [0u8; 1024];
+        let mut ancillary = RecvAncillary::new();
+        let (n, addr) = socket1
+            .recv_msg(
+                &mut [IoSliceMut::new(&mut buf1), IoSliceMut::new(&mut buf2)],
+                &mut ancillary,
+            )
+            .await?;
+        assert_eq!(n, LOREM_IPSUM.len());
+        assert_eq!(addr, socket2.get_ref().local_addr()?);
+        assert_eq!(&buf1[..], head);
+        assert_eq!(&buf2[..n - 10], tail);
+        assert!(!ancillary.is_truncated());
+
+        let msgs: Vec<_> = ancillary.messages().collect();
+        assert!(msgs.iter().any(|m| match m {
+            ControlMessage::PacketInfo(PacketInfo { addr, .. }) => *addr == Ipv4Addr::LOCALHOST,
+            _ => false,
+        }));
+        assert!(msgs.contains(&ControlMessage::Ttl(42)));
+        assert!(msgs
+            .iter()
+            .any(|m| matches!(m, ControlMessage::Timestamp(_))));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_send_recv_batch() -> io::Result<()> {
+    block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let addr1 = socket1.get_ref().local_addr()?;
+        let addr2 = socket2.get_ref().local_addr()?;
+
+        let lines: Vec<&[u8]> = LOREM_IPSUM.split(|&b| b == b'\n').collect();
+        let msgs: Vec<_> = lines.iter().map(|line| (*line, addr2)).collect();
+        assert_eq!(socket1.send_batch(&msgs).await?, lines.len());
+
+        let mut storage = vec![[0u8; 1024]; lines.len() + 1];
+        let mut received = Vec::new();
+        while received.len() < lines.len() {
+            let mut bufs: Vec<&mut [u8]> = storage.iter_mut().map(|b| &mut b[..]).collect();
+            let batch = socket2.recv_batch(&mut bufs).await?;
+            for ((len, addr), buf) in batch.into_iter().zip(bufs) {
+                assert_eq!(addr, addr1);
+                received.push(buf[..len].to_vec());
+            }
+        }
+        assert_eq!(received, lines);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_send_recv_batch() -> io::Result<()> {
+    block_on(async {
+        let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+
+        let lines: Vec<&[u8]> = LOREM_IPSUM.split(|&b| b == b'\n').collect();
+        assert_eq!(socket1.send_batch(&lines).await?, lines.len());
+
+        let mut storage = vec![[0u8; 1024]; lines.len()];
+        let mut bufs: Vec<&mut [u8]> = storage.iter_mut().map(|b| &mut b[..]).collect();
+        let lens = socket2.recv_batch(&mut bufs).await?;
+        assert_eq!(lens.len(), lines.len());
+        for ((len, buf), line) in lens.iter().zip(bufs).zip(&lines) {
+            assert_eq!(&buf[..*len], *line);
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_send_segments_recv_coalesced() -> io::Result<()> {
+    block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let addr2 = socket2.get_ref().local_addr()?;
+        socket2.set_gro(true)?;
+
+        let segment_size = 100;
+        let n = socket1
+            .send_segments(LOREM_IPSUM, segment_size, addr2)
+            .await?;
+        assert_eq!(n, LOREM_IPSUM.len());
+
+        let mut received = Vec::new();
+        let mut buf = vec![0u8; 65536];
+        while received.len() < LOREM_IPSUM.len() {
+            let (len, size, addr) = socket2.recv_coalesced(&mut buf).await?;
+            assert_eq!(addr, socket1.get_ref().local_addr()?);
+            for datagram in buf[..len].chunks(size) {
+                let expected = segment_size.min(LOREM_IPSUM.len() - received.len());
+                assert_eq!(datagram.len(), expected);
+                received.extend_from_slice(datagram);
+            }
+        }
+        assert_eq!(received, LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_multicast() -> io::Result<()> {
+    use std::net::Ipv4Addr;
+
+    block_on(async {
+        let group = Ipv4Addr::new(239, 255, 42, 99);
+        let receiver1 = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 0))?;
+        let port = receiver1.get_ref().local_addr()?.port();
+        let receiver2 = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, port))?;
+        receiver1.join_multicast_v4(group, Ipv4Addr::LOCALHOST)?;
+        receiver2.join_multicast_v4(group, Ipv4Addr::LOCALHOST)?;
+
+        let sender = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        sender.set_multicast_if_v4(Ipv4Addr::LOCALHOST)?;
+        sender.set_multicast_loop(true)?;
+        sender.set_multicast_ttl(1)?;
+        assert_eq!(sender.multicast_if_v4()?, Ipv4Addr::LOCALHOST);
+        assert!(sender.multicast_loop()?);
+        assert_eq!(sender.multicast_ttl()?, 1);
+
+        sender.send_to(LOREM_IPSUM, (group, port)).await?;
+
+        let mut buf = [0u8; 1024];
+        for receiver in &[&receiver1, &receiver2] {
+            let (n, addr) = receiver.recv_from(&mut buf).await?;
+            assert_eq!(&buf[..n], LOREM_IPSUM);
+            assert_eq!(addr, sender.get_ref().local_addr()?);
+        }
+
+        receiver1.leave_multicast_v4(group, Ipv4Addr::LOCALHOST)?;
+        receiver2.leave_multicast_v4(group, Ipv4Addr::LOCALHOST)?;
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_multicast_ssm() -> io::Result<()> {
+    use std::net::Ipv4Addr;
+
+    block_on(async {
+        let group = Ipv4Addr::new(232, 42, 42, 42);
+        let receiver = Async::<UdpSocket>::bind_multicast((Ipv4Addr::UNSPECIFIED, 0))?;
+        let port = receiver.get_ref().local_addr()?.port();
+        receiver.join_ssm_v4(Ipv4Addr::LOCALHOST, group, Ipv4Addr::LOCALHOST)?;
+
+        let sender = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        sender.set_multicast_if_v4(Ipv4Addr::LOCALHOST)?;
+        sender.send_to(LOREM_IPSUM, (group, port)).await?;
+
+        let mut buf = [0u8; 1024];
+        let (n, _) = receiver.recv_from(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        receiver.leave_ssm_v4(Ipv4Addr::LOCALHOST, group, Ipv4Addr::LOCALHOST)?;
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_send_recv_fds() -> io::Result<()> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::os::unix::io::AsRawFd;
+
+    block_on(async {
+        let dir = tempdir()?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(dir.path().join("file"))?;
+        file.write_all(LOREM_IPSUM)?;
+
+        let (stream1, stream2) = Async::<UnixStream>::pair()?;
+        stream1.send_with_fds(b"file", &[file.as_raw_fd()]).await?;
+
+        let mut buf = [0u8; 1024];
+        let (n, fds) = stream2.recv_with_fds(&mut buf, 4).await?;
+        assert_eq!(&buf[..n], b"file");
+        assert_eq!(fds.len(), 1);
+
+        let flags = unsafe { libc::fcntl(fds[0].as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+        let mut received = fds.into_iter().next().unwrap();
+        let mut contents = Vec::new();
+        received.seek(SeekFrom::Start(0))?;
+        received.read_to_end(&mut contents)?;
+        assert_eq!(contents, LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_recv_fds_truncated() -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    block_on(async {
+        let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+        let fd = socket1.get_ref().as_raw_fd();
+        let fds = [fd, fd, fd];
+        socket1.send_with_fds(LOREM_IPSUM, &fds).await?;
+        socket1.send_with_fds(LOREM_IPSUM, &[]).await?;
+
+        // The data is still delivered, but no more than `max_fds` file descriptors.
+        let mut buf = [0u8; 1024];
+        let (n, fds) = socket2.recv_with_fds(&mut buf, 1).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(fds.len(), 1);
+
+        let (n, fds) = socket2.recv_with_fds(&mut buf, 1).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert!(fds.is_empty());
+
+        // Alignment of the control buffer may leave room for more file descriptors than
+        // requested, but no more than `max_fds` are ever returned.
+        socket1.send_with_fds(LOREM_IPSUM, &[fd, fd]).await?;
+        let (n, fds) = socket2.recv_with_fds(&mut buf, 1).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(fds.len(), 1);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_stream_recv_fds_truncated() -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    block_on(async {
+        let (stream1, stream2) = Async::<UnixStream>::pair()?;
+        let fd = stream1.get_ref().as_raw_fd();
+        stream1.send_with_fds(b"first", &[fd, fd, fd]).await?;
+
+        // Data on a stream isn't lost when file descriptors are dropped.
+        let mut buf = [0u8; 5];
+        let (n, fds) = stream2.recv_with_fds(&mut buf, 1).await?;
+        assert_eq!(&buf[..n], b"first");
+        assert_eq!(fds.len(), 1);
+
+        stream1.send_with_fds(b"second", &[]).await?;
+        let mut buf = [0u8; 6];
+        let (n, fds) = stream2.recv_with_fds(&mut buf, 1).await?;
+        assert_eq!(&buf[..n], b"second");
+        assert!(fds.is_empty());
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_peer_cred() -> io::Result<()> {
+    use async_io::UCred;
+
+    let (stream1, stream2) = Async::<UnixStream>::pair()?;
+    let cred = UCred::current();
+    for stream in &[stream1, stream2] {
+        let peer = stream.peer_cred()?;
+        assert_eq!(peer.uid, cred.uid);
+        assert_eq!(peer.gid, cred.gid);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(peer.pid, cred.pid);
+    }
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn uds_send_recv_cred() -> io::Result<()> {
+    use async_io::UCred;
+
+    block_on(async {
+        let (socket1, socket2) = Async::<UnixDatagram>::pair()?;
+        socket2.set_pass_cred(true)?;
+
+        let cred = UCred::current();
+        socket1.send_with_cred(LOREM_IPSUM, &cred).await?;
+
+        let mut buf = [0u8; 1024];
+        let (n, received) = socket2.recv_with_cred(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(received, Some(cred));
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn uds_abstract() -> io::Result<()> {
+    block_on(async {
+        let name = format!("async-io-test-{}", std::process::id());
+
+        let listener = Async::<UnixListener>::bind_abstract(&name)?;
+        let task = spawn(async move { listener.accept().await });
+        let mut stream1 = Async::<UnixStream>::connect_abstract(&name).await?;
+        let (mut stream2, _) = task.await?;
+        stream1.write_all(LOREM_IPSUM).await?;
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream2.read_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        let socket1 = Async::<UnixDatagram>::bind_abstract(format!("{}-dgram", name))?;
+        let socket2 = Async::<UnixDatagram>::unbound()?;
+        socket2
+            .send_to_abstract(LOREM_IPSUM, format!("{}-dgram", name))
+            .await?;
+        let mut buf = [0u8; 1024];
+        let n = socket1.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+#[test]
+fn uds_seqpacket() -> io::Result<()> {
+    use async_io::{UnixSeqpacket, UnixSeqpacketListener};
+
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("socket");
+
+        let listener = Async::<UnixSeqpacketListener>::bind(&path)?;
+        let task = spawn(async move { listener.accept().await });
+        let conn1 = Async::<UnixSeqpacket>::connect(&path).await?;
+        let conn2 = task.await?;
+
+        // Every message is received on its own, even when several are queued.
+        let lines: Vec<&[u8]> = LOREM_IPSUM.split(|&b| b == b'\n').collect();
+        for line in &lines {
+            assert_eq!(conn1.send(line).await?, line.len());
+        }
+        let mut buf = [0u8; 1024];
+        for line in &lines {
+            let n = conn2.recv(&mut buf).await?;
+            assert_eq!(&buf[..n], *line);
+        }
+
+        // Receiving into a short buffer truncates the message.
+        let (conn1, conn2) = Async::<UnixSeqpacket>::pair()?;
+        conn1.send(LOREM_IPSUM).await?;
+        conn1.send(b"next").await?;
+        assert_eq!(conn2.recv(&mut buf[..5]).await?, 5);
+        assert_eq!(&buf[..5], &LOREM_IPSUM[..5]);
+        let n = conn2.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], b"next");
+
+        // Dropping one end signals end of stream to the other.
+        drop(conn1);
+        assert_eq!(conn2.recv(&mut buf).await?, 0);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_connect_send_recv() -> io::Result<()> {
+    block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let addr1 = socket1.get_ref().local_addr()?;
+
+        let socket2 = Async::<UdpSocket>::connect(([127, 0, 0, 1], 0), addr1).await?;
+        let addr2 = socket2.get_ref().local_addr()?;
+        assert_eq!(socket2.get_ref().peer_addr()?, addr1);
+
+        socket2.send(LOREM_IPSUM).await?;
+        let mut buf = [0u8; 1024];
+        let (n, addr) = socket1.recv_from(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(addr, addr2);
+
+        socket1.send_to(LOREM_IPSUM, addr2).await?;
+        let n = socket2.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_connect_refused() -> io::Result<()> {
+    block_on(async {
+        // Find a port nobody listens on.
+        let addr = UdpSocket::bind(("127.0.0.1", 0))?.local_addr()?;
+
+        let socket = Async::<UdpSocket>::connect(([127, 0, 0, 1], 0), addr).await?;
+        socket.send(LOREM_IPSUM).await?;
+
+        let mut buf = [0u8; 1024];
+        let err = socket.recv(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_datagram_connect() -> io::Result<()> {
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("socket");
+
+        let socket1 = Async::<UnixDatagram>::bind(&path)?;
+        let socket2 = Async::<UnixDatagram>::connect(&path).await?;
+
+        socket2.send(LOREM_IPSUM).await?;
+        let mut buf = [0u8; 1024];
+        let n = socket1.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_connect_with_data() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        // Fast Open may be disabled by the kernel, in which case a regular connect is used.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        listener.set_fastopen(16)?;
+        let addr = listener.get_ref().local_addr()?;
+
+        // The first connection gets a cookie, the second one may send data in its SYN.
+        for _ in 0..2 {
+            let task = spawn(async move {
+                let mut stream = Async::<TcpStream>::connect_with_data(addr, LOREM_IPSUM).await?;
+                stream.write_all(b"!").await?;
+                io::Result::Ok(())
+            });
+
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await?;
+            assert_eq!(&buf[..LOREM_IPSUM.len()], LOREM_IPSUM);
+            assert_eq!(&buf[LOREM_IPSUM.len()..], b"!");
+            task.await?;
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_socket_options() -> io::Result<()> {
+    use async_io::TcpKeepalive;
+
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { Async::<TcpStream>::connect(addr).await });
+        let (stream, _) = listener.accept().await?;
+        let _client = task.await?;
+
+        let keepalive = TcpKeepalive::new()
+            .with_time(Duration::from_secs(60))
+            .with_interval(Duration::from_secs(10))
+            .with_retries(5);
+        stream.set_keepalive(Some(keepalive))?;
+        let actual = stream.keepalive()?.unwrap();
+        assert_eq!(actual.time(), Some(Duration::from_secs(60)));
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+        {
+            assert_eq!(actual.interval(), Some(Duration::from_secs(10)));
+            assert_eq!(actual.retries(), Some(5));
+        }
+        stream.set_keepalive(None)?;
+        assert_eq!(stream.keepalive()?, None);
+
+        stream.set_linger(Some(Duration::from_secs(5)))?;
+        assert_eq!(stream.linger()?, Some(Duration::from_secs(5)));
+        stream.set_linger(None)?;
+        assert_eq!(stream.linger()?, None);
+
+        stream.set_nodelay(true)?;
+        assert!(stream.nodelay()?);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            stream.set_user_timeout(Some(Duration::from_secs(30)))?;
+            assert_eq!(stream.user_timeout()?, Some(Duration::from_secs(30)));
+            stream.set_user_timeout(None)?;
+            assert_eq!(stream.user_timeout()?, None);
+
+            // Timeouts too long for the kernel are clamped.
+            stream.set_user_timeout(Some(Duration::from_secs(30 * 24 * 60 * 60)))?;
+            let max = Duration::from_millis(libc::c_int::MAX as u64);
+            assert_eq!(stream.user_timeout()?, Some(max));
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_accept_with_options() -> io::Result<()> {
+    use async_io::{TcpKeepalive, TcpOptions};
+
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { Async::<TcpStream>::connect(addr).await });
+
+        let options = TcpOptions::new()
+            .with_keepalive(TcpKeepalive::new().with_time(Duration::from_secs(60)))
+            .with_linger(Duration::from_secs(5))
+            .with_nodelay(true);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let options = options.with_user_timeout(Duration::from_secs(30));
+
+        let (stream, _) = listener.accept_with(&options).await?;
+        let _client = task.await?;
+
+        assert_eq!(
+            stream.keepalive()?.and_then(|k| k.time()),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(stream.linger()?, Some(Duration::from_secs(5)));
+        assert!(stream.nodelay()?);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(stream.user_timeout()?, Some(Duration::from_secs(30)));
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn tcp_sendfile() -> io::Result<()> {
+    use std::io::Write;
+
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("file");
+        let contents = LOREM_IPSUM.repeat(1000);
+        std::fs::File::create(&path)?.write_all(&contents)?;
+        let file = std::fs::File::open(&path)?;
+
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move {
+            let mut stream = Async::<TcpStream>::connect(addr).await?;
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await?;
+            io::Result::Ok(buf)
+        });
+
+        let (stream, _) = listener.accept().await?;
+        let offset = 100;
+        let n = stream
+            .sendfile(&file, offset as u64, contents.len())
+            .await?;
+        assert_eq!(n, contents.len() - offset);
+        drop(stream);
+
+        assert_eq!(task.await?, &contents[offset..]);
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_bidirectional() -> io::Result<()> {
+    block_on(async {
+        // An echo server.
+        let server = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let server_addr = server.get_ref().local_addr()?;
+        let echo = spawn(async move {
+            let (mut stream, _) = server.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).await?;
+                if n == 0 {
+                    return stream.get_ref().shutdown(Shutdown::Write);
+                }
+                stream.write_all(&buf[..n]).await?;
+            }
+        });
+
+        // A proxy forwarding a single connection to the echo server.
+        let proxy = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let proxy_addr = proxy.get_ref().local_addr()?;
+        let forward = spawn(async move {
+            let (client, _) = proxy.accept().await?;
+            let upstream = Async::<TcpStream>::connect(server_addr).await?;
+            async_io::copy_bidirectional(&client, &upstream).await
+        });
+
+        let contents = LOREM_IPSUM.repeat(1000);
+        let stream = Arc::new(Async::<TcpStream>::connect(proxy_addr).await?);
+        let writer = {
+            let stream = stream.clone();
+            let contents = contents.clone();
+            spawn(async move {
+                (&*stream).write_all(&contents).await?;
+                stream.get_ref().shutdown(Shutdown::Write)
+            })
+        };
+
+        let mut echoed = Vec::new();
+        (&*stream).read_to_end(&mut echoed).await?;
+        assert_eq!(echoed, contents);
+
+        writer.await?;
+        echo.await?;
+        let (sent, received) = forward.await?;
+        assert_eq!(sent, contents.len() as u64);
+        assert_eq!(received, contents.len() as u64);
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tcp_send_zerocopy() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let contents = LOREM_IPSUM.repeat(10_000);
+
+        let expected = contents.len() * 2;
+        let task = spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await?;
+            io::Result::Ok(buf)
+        });
+
+        // Send the same buffer twice to check that it's returned after completion.
+        let mut stream = Async::<TcpStream>::connect(addr).await?;
+        let buf = stream.send_zerocopy(contents).await?;
+        let buf = stream.send_zerocopy(buf).await?;
+        drop(stream);
+
+        let received = task.await?;
+        assert_eq!(received.len(), expected);
+        assert_eq!(&received[..buf.len()], &buf[..]);
+        assert_eq!(&received[buf.len()..], &buf[..]);
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tcp_send_zerocopy_cancelled() -> io::Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // A buffer that records when it gets dropped.
+    struct Buf(Vec<u8>, Arc<AtomicBool>);
+
+    impl AsRef<[u8]> for Buf {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl Drop for Buf {
+        fn drop(&mut self) {
+            self.1.store(true, Ordering::SeqCst);
+        }
+    }
+
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await
+        });
+
+        // Cancel a send after it has started.
+        let mut stream = Async::<TcpStream>::connect(addr).await?;
+        let dropped = Arc::new(AtomicBool::new(false));
+        let buf = Buf(LOREM_IPSUM.repeat(10_000), dropped.clone());
+        if future::poll_once(stream.send_zerocopy(buf)).await.is_none() {
+            assert!(!dropped.load(Ordering::SeqCst));
+        }
+
+        // The socket drops the buffer once the reactor observes its completion while waiting
+        // for any event. Readers aren't woken over and over by the pending notifications.
+        for _ in 0..100 {
+            if dropped.load(Ordering::SeqCst) {
+                break;
+            }
+            let timeout = async {
+                Timer::new(Duration::from_millis(10)).await;
+                Ok(())
+            };
+            stream.readable().or(timeout).await?;
+        }
+        assert!(dropped.load(Ordering::SeqCst));
+
+        // Later sends still complete.
+        stream.send_zerocopy(LOREM_IPSUM).await?;
+
+        drop(stream);
+        task.await?;
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe() -> io::Result<()> {
+    block_on(async {
+        let (reader, mut writer) = async_io::pipe()?;
+        let reader = Arc::new(reader);
+
+        let task = spawn({
+            let reader = reader.clone();
+            async move {
+                let mut buf = Vec::new();
+                (&*reader).read_to_end(&mut buf).await?;
+                io::Result::Ok(buf)
+            }
+        });
+
+        writer.write_all(LOREM_IPSUM).await?;
+        drop(writer);
+        assert_eq!(task.await?, LOREM_IPSUM);
+
+        // Writing after the reader is gone fails with a broken pipe.
+        let (reader, mut writer) = async_io::pipe()?;
+        drop(reader);
+        let err = writer.write_all(LOREM_IPSUM).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn process() -> io::Result<()> {
+    use async_io::process::{Command, Stdio};
+
+    block_on(async {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        child.stdin.as_mut().unwrap().write_all(LOREM_IPSUM).await?;
+        let output = child.output().await?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout, LOREM_IPSUM);
+        assert!(output.stderr.is_empty());
+
+        // Wait for several processes at once, in the reverse order of their exit.
+        let mut children = Vec::new();
+        for i in 0..4 {
+            let code = format!("sleep 0.{}; exit {}", i, i);
+            children.push(Command::new("sh").arg("-c").arg(code).spawn()?);
+        }
+        for (i, child) in children.iter_mut().enumerate().rev() {
+            assert_eq!(child.status().await?.code(), Some(i as i32));
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn pty() -> io::Result<()> {
+    use async_io::process::Command;
+    use std::os::unix::io::AsRawFd;
+
+    block_on(async {
+        let (mut master, slave) = async_io::pty::open()?;
+        assert!(slave.path().exists());
+
+        // The master side isn't inherited by child processes.
+        let flags = unsafe { libc::fcntl(master.as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+        master.get_ref().resize(24, 80)?;
+        assert_eq!(master.get_ref().window_size()?, (24, 80));
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg("read line; echo \"got $line\"; stty size; tty")
+            .pty(&slave)?;
+        let mut child = cmd.spawn()?;
+        drop(cmd);
+        drop(slave);
+
+        master.write_all(b"hello\n").await?;
+        let mut output = String::new();
+        master.read_to_string(&mut output).await?;
+        assert!(child.status().await?.success());
+
+        // The terminal echoes input and translates newlines.
+        assert!(output.contains("got hello\r\n"));
+        assert!(output.contains("24 80\r\n"));
+        assert!(!output.contains("not a tty"));
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn eventfd() -> io::Result<()> {
+    use async_io::eventfd::EventFd;
+
+    block_on(async {
+        let event = Arc::new(Async::new(EventFd::new(0)?)?);
+
+        // A thread outside the runtime wakes the waiting task.
+        let notifier = event.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            notifier.notify(1)
+        });
+        assert_eq!(event.wait().await?, 1);
+        handle.join().unwrap()?;
+
+        // Notifications add up until they are read.
+        event.notify(2)?;
+        event.notify(3)?;
+        assert_eq!(event.wait().await?, 5);
+
+        // In semaphore mode, every wait consumes one.
+        let event = Async::new(EventFd::semaphore(0)?)?;
+        event.notify(2)?;
+        assert_eq!(event.wait().await?, 1);
+        assert_eq!(event.wait().await?, 1);
+        let timeout = async {
+            Timer::new(Duration::from_millis(50)).await;
+            Ok(0)
+        };
+        assert_eq!(event.wait().or(timeout).await?, 0);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn timerfd() -> io::Result<()> {
+    use async_io::timerfd::{Clock, TimerFd};
+
+    block_on(async {
+        let timer = Async::new(TimerFd::new(Clock::Boottime)?)?;
+        timer.set(Duration::from_millis(10), Some(Duration::from_millis(10)))?;
+
+        // Expirations are counted while nobody waits.
+        thread::sleep(Duration::from_millis(55));
+        let count = timer.wait().await?;
+        assert!(count >= 4, "count = {}", count);
+
+        let mut expirations = timer.expirations();
+        assert!(expirations.next().await.unwrap()? >= 1);
+
+        // A disarmed timer never fires.
+        timer.disarm()?;
+        let timeout = async {
+            Timer::new(Duration::from_millis(50)).await;
+            Ok(0)
+        };
+        assert_eq!(timer.wait().or(timeout).await?, 0);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn inotify() -> io::Result<()> {
+    use async_io::inotify::{Inotify, WatchMask};
+
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("config");
+
+        let inotify = Async::<Inotify>::init()?;
+        let mask = WatchMask::CREATE | WatchMask::CLOSE_WRITE | WatchMask::DELETE;
+        let wd = inotify.add_watch(dir.path(), mask)?;
+        let mut events = inotify.events();
+
+        std::fs::write(&path, LOREM_IPSUM)?;
+        std::fs::remove_file(&path)?;
+
+        for expected in [WatchMask::CREATE, WatchMask::CLOSE_WRITE, WatchMask::DELETE] {
+            let event = events.next().await.unwrap()?;
+            assert_eq!(event.wd, Some(wd));
+            assert_eq!(event.mask, expected);
+            assert_eq!(event.name.as_deref(), Some("config".as_ref()));
+        }
+
+        // Removing the watch is reported too.
+        inotify.remove_watch(wd)?;
+        let event = events.next().await.unwrap()?;
+        assert_eq!(event.wd, Some(wd));
+        assert!(event.mask.contains(WatchMask::IGNORED));
+        assert_eq!(event.name, None);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn netlink() -> io::Result<()> {
+    use async_io::netlink::{NetlinkMessage, NetlinkSocket, NETLINK_ROUTE, NLM_F_ACK, NLM_F_DUMP};
+    use async_io::netlink::{RTM_GETLINK, RTM_NEWLINK};
+
+    block_on(async {
+        let msg = NetlinkMessage::new(RTM_GETLINK, NLM_F_DUMP, vec![1, 2, 3]);
+        let bytes = msg.to_bytes();
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(NetlinkMessage::parse(&bytes)?, vec![msg]);
+        assert!(NetlinkMessage::parse(&bytes[..10]).is_err());
+
+        let socket = Async::<NetlinkSocket>::bind(NETLINK_ROUTE, 0)?;
+        assert_ne!(socket.get_ref().port_id()?, 0);
+
+        // Dump all interfaces, which include at least the loopback interface.
+        let seq = socket.request(RTM_GETLINK, NLM_F_DUMP, &[0; 16]).await?;
+        let mut links = socket.responses(seq);
+        let mut count = 0;
+        while let Some(msg) = links.next().await {
+            let msg = msg?;
+            assert_eq!(msg.kind, RTM_NEWLINK);
+            assert_eq!(msg.seq, seq);
+            count += 1;
+        }
+        assert!(count >= 1);
+
+        // Requesting an interface that doesn't exist is an error.
+        let mut payload = [0; 16];
+        payload[4..8].copy_from_slice(&i32::MAX.to_ne_bytes());
+        let next = socket.request(RTM_GETLINK, NLM_F_ACK, &payload).await?;
+        assert_ne!(next, seq);
+        let mut responses = socket.responses(next);
+        let err = responses.next().await.unwrap().unwrap_err();
+        assert!(err.raw_os_error().is_some());
+        assert!(responses.next().await.is_none());
+
+        // Replies to concurrent requests go to their own streams.
+        let collect = |seq| {
+            let socket = &socket;
+            async move {
+                let mut count = 0;
+                let mut responses = socket.responses(seq);
+                while let Some(msg) = responses.next().await {
+                    assert_eq!(msg?.seq, seq);
+                    count += 1;
+                }
+                io::Result::Ok(count)
+            }
+        };
+        // Only one dump runs at a time, so the other request is for the loopback interface.
+        let mut loopback = [0; 16];
+        loopback[4..8].copy_from_slice(&1i32.to_ne_bytes());
+        let seq1 = socket.request(RTM_GETLINK, NLM_F_DUMP, &[0; 16]).await?;
+        let seq2 = socket.request(RTM_GETLINK, 0, &loopback).await?;
+        let counts = future::try_join(collect(seq1), collect(seq2)).await?;
+        assert_eq!(counts, (count, 1));
+
+        // The rest of a reply is discarded when its stream is dropped.
+        let mut messages = socket.messages();
+        let seq = socket.request(RTM_GETLINK, NLM_F_DUMP, &[0; 16]).await?;
+        let first = socket.responses(seq).next().await.unwrap()?;
+        assert_eq!(first.kind, RTM_NEWLINK);
+        let seq = socket.request(RTM_GETLINK, 0, &loopback).await?;
+        assert_eq!(collect(seq).await?, 1);
+        assert!(future::poll_once(messages.next()).await.is_none());
+
+        // Messages from other processes are dropped unless accepted.
+        let other = NetlinkSocket::new(NETLINK_ROUTE)?;
+        let port = socket.get_ref().port_id()?;
+        let forged = NetlinkMessage::new(RTM_NEWLINK, 0, vec![0; 16]).to_bytes();
+        other.send_to(&forged, port)?;
+        let seq = socket.request(RTM_GETLINK, 0, &loopback).await?;
+        assert_eq!(collect(seq).await?, 1);
+        assert!(future::poll_once(messages.next()).await.is_none());
+
+        socket.get_ref().set_accept_processes(true);
+        other.send_to(&forged, port)?;
+        let msg = messages.next().await.unwrap()?;
+        assert_eq!(msg.kind, RTM_NEWLINK);
+        assert_eq!(msg.seq, 0);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn stdio() -> io::Result<()> {
+    use async_io::process::{Command, Stdio};
+
+    // When spawned by the test below, copy stdin to stdout.
+    if std::env::var_os("ASYNC_IO_STDIO_CHILD").is_some() {
+        return block_on(async {
+            let mut stdin = async_io::stdin()?;
+            let mut stdout = async_io::stdout()?;
+            let mut buf = Vec::new();
+            stdin.read_to_end(&mut buf).await?;
+            stdout.write_all(b"<<").await?;
+            stdout.write_all(&buf).await?;
+            stdout.write_all(b">>").await?;
+            stdout.flush().await
+        });
+    }
+
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("input");
+        std::fs::write(&path, LOREM_IPSUM)?;
+        let expected = [&b"<<"[..], LOREM_IPSUM, b">>"].concat();
+
+        let child = || {
+            let mut cmd = Command::new(std::env::current_exe().unwrap());
+            cmd.args(["stdio", "--exact", "--nocapture", "--test-threads=1"])
+                .env("ASYNC_IO_STDIO_CHILD", "1");
+            cmd
+        };
+
+        // Standard input is a regular file and standard output is a pipe.
+        let output = child().stdin(std::fs::File::open(&path)?).output().await?;
+        assert!(output.status.success());
+        assert!(output
+            .stdout
+            .windows(expected.len())
+            .any(|w| w == &expected[..]));
+
+        // Standard input is a pipe and standard output is a regular file.
+        let out_path = dir.path().join("output");
+        let mut child = child()
+            .stdin(Stdio::piped())
+            .stdout(std::fs::File::create(&out_path)?)
+            .spawn()?;
+        child.stdin.as_mut().unwrap().write_all(LOREM_IPSUM).await?;
+        assert!(child.status().await?.success());
+        let output = std::fs::read(&out_path)?;
+        assert!(output.windows(expected.len()).any(|w| w == &expected[..]));
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn stdio_pty() -> io::Result<()> {
+    use async_io::process::Command;
+
+    fn is_nonblocking(fd: i32) -> bool {
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        assert_ne!(flags, -1);
+        flags & libc::O_NONBLOCK != 0
+    }
+
+    // When spawned by the test below, standard input and output share a terminal.
+    if std::env::var_os("ASYNC_IO_STDIO_PTY_CHILD").is_some() {
+        return block_on(async {
+            let stdin = async_io::stdin()?;
+            let mut stdout = async_io::stdout()?;
+            assert!(is_nonblocking(0));
+
+            // Standard output still needs non-blocking mode.
+            drop(stdin);
+            assert!(is_nonblocking(1));
+            stdout.write_all(b"<<ok>>").await?;
+            stdout.flush().await?;
+
+            drop(stdout);
+            assert!(!is_nonblocking(0));
+            Ok(())
+        });
+    }
+
+    block_on(async {
+        let (mut master, slave) = async_io::pty::open()?;
+        let mut cmd = Command::new(std::env::current_exe().unwrap());
+        cmd.args(["stdio_pty", "--exact", "--nocapture", "--test-threads=1"])
+            .env("ASYNC_IO_STDIO_PTY_CHILD", "1")
+            .pty(&slave)?;
+        let mut child = cmd.spawn()?;
+        drop(cmd);
+        drop(slave);
+
+        let mut output = String::new();
+        master.read_to_string(&mut output).await?;
+        assert!(child.status().await?.success(), "{}", output);
+        assert!(output.contains("<<ok>>"));
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn blocking_fallback() -> io::Result<()> {
+    use std::fs::{File, OpenOptions};
+    use std::io::SeekFrom;
+
+    block_on(async {
+        let dir = tempdir()?;
+        let path = dir.path().join("file");
+        std::fs::write(&path, LOREM_IPSUM)?;
+
+        // Epoll rejects regular files with a clear error.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let err = Async::new(File::open(&path)?).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+
+        // They can still be read and written on the thread pool.
+        let mut file = Async::new_blocking_fallback(File::open(&path)?)?;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert!(file.is_blocking());
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+        let mut file = Async::new_blocking_fallback(file)?;
+        file.write_all(LOREM_IPSUM).await?;
+        file.flush().await?;
+        assert_eq!(std::fs::read(&path)?, [LOREM_IPSUM, LOREM_IPSUM].concat());
+
+        // Reads and seeks wait for earlier writes.
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut file = Async::new_blocking_fallback(file)?;
+        file.write_all(LOREM_IPSUM).await?;
+        assert_eq!(file.read(&mut [0; 16]).await?, 0);
+        file.seek(SeekFrom::Start(0)).await?;
+        let mut buf = vec![0; 16];
+        file.read_exact(&mut buf).await?;
+        assert_eq!(buf, &LOREM_IPSUM[..16]);
+        assert_eq!(file.seek(SeekFrom::Current(0)).await?, 16);
+
+        // Supported handles are registered in the reactor.
+        let (stream1, stream2) = UnixStream::pair()?;
+        let mut stream1 = Async::new_blocking_fallback(stream1)?;
+        let mut stream2 = Async::new_blocking_fallback(stream2)?;
+        assert!(!stream1.is_blocking());
+        stream1.write_all(LOREM_IPSUM).await?;
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream2.read_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn signals() -> io::Result<()> {
+    use async_io::process::Command;
+    use async_io::signal::{Signals, SIGUSR1, SIGUSR2};
+
+    block_on(async {
+        let mut signals1 = Signals::new(&[SIGUSR1])?;
+        let mut signals2 = Signals::new(&[SIGUSR1, SIGUSR2])?;
+
+        let pid = std::process::id().to_string();
+        let status = Command::new("kill").arg("-USR1").arg(&pid).status().await?;
+        assert!(status.success());
+
+        // Every listener receives the signal.
+        assert_eq!(signals1.next().await, Some(SIGUSR1));
+        assert_eq!(signals2.next().await, Some(SIGUSR1));
+
+        let status = Command::new("kill").arg("-USR2").arg(&pid).status().await?;
+        assert!(status.success());
+        assert_eq!(signals2.next().await, Some(SIGUSR2));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_ready() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let mut client = Async::<TcpStream>::connect(addr).await?;
+        let mut stream = listener.accept().await?.0;
+
+        // Waiting for no events returns right away without consuming any readiness.
+        let empty = future::poll_once(Box::pin(stream.ready(Interest::EMPTY))).await;
+        assert_eq!(empty.transpose()?, Some(Readiness::default()));
+
+        let readiness = client
+            .ready(Interest::READABLE | Interest::WRITABLE)
+            .await?;
+        assert!(readiness.is_writable());
+        assert!(!readiness.is_write_closed());
+
+        client.write_all(LOREM_IPSUM).await?;
+        let readiness = stream.ready(Interest::READABLE).await?;
+        assert!(readiness.is_readable());
+        assert!(!readiness.is_read_closed());
+
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream.read_exact(&mut buf).await?;
+
+        // Half-closing the connection is reported separately from incoming data.
+        client.shutdown(Shutdown::Write)?;
+        let readiness = stream.ready(Interest::READABLE).await?;
+        assert!(readiness.is_read_closed());
+        assert!(!readiness.is_error());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_ready_interleaved() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let mut client = Async::<TcpStream>::connect(addr).await?;
+        let stream = listener.accept().await?.0;
+
+        // Start waiting for readability before any data arrives.
+        let mut read = Box::pin(stream.ready(Interest::READABLE));
+        assert!(future::poll_once(&mut read).await.is_none());
+
+        // Let the reactor move past the tick in which the wait started, deliver the readability
+        // event, and then wait for writability.
+        Timer::new(Duration::from_millis(100)).await;
+        client.write_all(LOREM_IPSUM).await?;
+        Timer::new(Duration::from_millis(100)).await;
+        let readiness = stream.ready(Interest::WRITABLE).await?;
+        assert!(readiness.is_writable());
+
+        // The writability event must not hide the earlier readability event.
+        let readiness = read.await?;
+        assert!(readiness.is_readable());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_ready_consumed() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let mut client = Async::<TcpStream>::connect(addr).await?;
+        let mut stream = listener.accept().await?.0;
+
+        // A read that waits for data consumes the readability event it observes.
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        let mut read = Box::pin(stream.read_exact(&mut buf));
+        assert!(future::poll_once(&mut read).await.is_none());
+        Timer::new(Duration::from_millis(100)).await;
+        client.write_all(LOREM_IPSUM).await?;
+        read.await?;
+
+        // Only writability is reported when it is the only event.
+        let readiness = stream
+            .ready(Interest::READABLE | Interest::WRITABLE)
+            .await?;
+        assert!(readiness.is_writable());
+        assert!(!readiness.is_readable());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_shutdown_wakes_reader() -> io::Result<()> {
+    block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let _client = Async::<TcpStream>::connect(addr).await?;
+        let stream = Arc::new(listener.accept().await?.0);
+
+        let reader = spawn({
+            let stream = stream.clone();
+            async move {
+                let mut buf = [0u8; 1024];
+                (&*stream).read(&mut buf).await
+            }
+        });
+
+        // Let the reader block before shutting down.
+        Timer::new(Duration::from_millis(50)).await;
+        stream.shutdown(Shutdown::Read)?;
+        assert_eq!(reader.await?, 0);
+
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn uds_shutdown_wakes_reader() -> io::Result<()> {
+    block_on(async {
+        let (stream1, _stream2) = Async::<UnixStream>::pair()?;
+        let stream1 = Arc::new(stream1);
+
+        let reader = spawn({
+            let stream = stream1.clone();
+            async move {
+                let mut buf = [0u8; 1024];
+                (&*stream).read(&mut buf).await
+            }
+        });
+
+        // Let the reader block before shutting down.
+        Timer::new(Duration::from_millis(50)).await;
+        stream1.shutdown(Shutdown::Both)?;
+        assert_eq!(reader.await?, 0);
+
+        Ok(())
+    })
+}